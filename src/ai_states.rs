@@ -0,0 +1,234 @@
+// the states a monster can be in and the graphs that say how it moves between
+// them, every monster picks a graph when it is spawned
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AiState {
+    // stands where it is
+    Idle,
+    // takes a random step now and then
+    Wander,
+    // walks a loop of cells around its home
+    Patrol,
+    // stays close to its leader
+    Follow,
+    Chase,
+    Flee,
+    // walks to where it last saw its target and looks around there until
+    // it forgets about it
+    Search,
+    ReturnHome,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Trigger {
+    // it can see something it is hostile to
+    SeesTarget,
+    // it can see something it is hostile to and is down to a quarter of its
+    // health
+    Threatened,
+    // it can not see anything it is hostile to
+    NoTarget,
+    // it got to where the state was taking it
+    Arrived,
+    // it can not see a target but knows where one is, from hearing it or
+    // from the rest of its pack
+    Alerted,
+    // it no longer remembers where its target was
+    Forgot,
+    // its pack has lost too much and is running
+    PackRetreat,
+    // it has been in the state for this many turns
+    After(usize),
+}
+
+pub struct Transition {
+    pub from: &'static [AiState],
+    pub trigger: Trigger,
+    pub to: AiState,
+}
+
+pub struct StateGraph {
+    pub start: AiState,
+    // the first transition out of the current state that fires is taken
+    pub transitions: &'static [Transition],
+}
+
+// walks about until it sees something and never runs
+pub const SHAMBLER: StateGraph = StateGraph {
+    start: AiState::Wander,
+    transitions: &[
+        Transition {
+            from: &[AiState::Wander, AiState::Search],
+            trigger: Trigger::SeesTarget,
+            to: AiState::Chase,
+        },
+        Transition {
+            from: &[AiState::Wander],
+            trigger: Trigger::Alerted,
+            to: AiState::Search,
+        },
+        Transition {
+            from: &[AiState::Chase],
+            trigger: Trigger::NoTarget,
+            to: AiState::Search,
+        },
+        Transition {
+            from: &[AiState::Search],
+            trigger: Trigger::Forgot,
+            to: AiState::Wander,
+        },
+    ],
+};
+
+// walks its rounds, runs when it is hurt and goes back to its post when it
+// loses track of its target
+pub const SENTRY: StateGraph = StateGraph {
+    start: AiState::Patrol,
+    transitions: &[
+        Transition {
+            from: &[
+                AiState::Patrol,
+                AiState::Chase,
+                AiState::Search,
+                AiState::ReturnHome,
+            ],
+            trigger: Trigger::Threatened,
+            to: AiState::Flee,
+        },
+        Transition {
+            from: &[AiState::Patrol, AiState::Search, AiState::ReturnHome],
+            trigger: Trigger::SeesTarget,
+            to: AiState::Chase,
+        },
+        Transition {
+            from: &[AiState::Patrol],
+            trigger: Trigger::Alerted,
+            to: AiState::Search,
+        },
+        Transition {
+            from: &[AiState::Chase],
+            trigger: Trigger::NoTarget,
+            to: AiState::Search,
+        },
+        Transition {
+            from: &[AiState::Flee],
+            trigger: Trigger::NoTarget,
+            to: AiState::ReturnHome,
+        },
+        Transition {
+            from: &[AiState::Search],
+            trigger: Trigger::Forgot,
+            to: AiState::ReturnHome,
+        },
+        Transition {
+            from: &[AiState::ReturnHome],
+            trigger: Trigger::Arrived,
+            to: AiState::Patrol,
+        },
+    ],
+};
+
+// nervous, it runs when hurt and heads back to its nest instead of looking
+// for what it lost
+pub const SKULKER: StateGraph = StateGraph {
+    start: AiState::Idle,
+    transitions: &[
+        Transition {
+            from: &[AiState::Idle, AiState::Chase, AiState::ReturnHome],
+            trigger: Trigger::Threatened,
+            to: AiState::Flee,
+        },
+        Transition {
+            from: &[AiState::Idle, AiState::ReturnHome],
+            trigger: Trigger::SeesTarget,
+            to: AiState::Chase,
+        },
+        Transition {
+            from: &[AiState::Chase, AiState::Flee],
+            trigger: Trigger::NoTarget,
+            to: AiState::ReturnHome,
+        },
+        Transition {
+            from: &[AiState::ReturnHome],
+            trigger: Trigger::Arrived,
+            to: AiState::Idle,
+        },
+        // it settles down where it is if the way home is blocked
+        Transition {
+            from: &[AiState::ReturnHome],
+            trigger: Trigger::After(30),
+            to: AiState::Idle,
+        },
+    ],
+};
+
+// hunts as a group, the whole pack gives up and runs once it has been
+// beaten down
+pub const PACK_HUNTER: StateGraph = StateGraph {
+    start: AiState::Idle,
+    transitions: &[
+        Transition {
+            from: &[
+                AiState::Idle,
+                AiState::Chase,
+                AiState::Search,
+                AiState::ReturnHome,
+            ],
+            trigger: Trigger::PackRetreat,
+            to: AiState::Flee,
+        },
+        Transition {
+            from: &[AiState::Idle, AiState::Search, AiState::ReturnHome],
+            trigger: Trigger::SeesTarget,
+            to: AiState::Chase,
+        },
+        Transition {
+            from: &[AiState::Idle],
+            trigger: Trigger::Alerted,
+            to: AiState::Search,
+        },
+        Transition {
+            from: &[AiState::Chase],
+            trigger: Trigger::NoTarget,
+            to: AiState::Search,
+        },
+        Transition {
+            from: &[AiState::Flee],
+            trigger: Trigger::NoTarget,
+            to: AiState::ReturnHome,
+        },
+        Transition {
+            from: &[AiState::Search],
+            trigger: Trigger::Forgot,
+            to: AiState::ReturnHome,
+        },
+        Transition {
+            from: &[AiState::ReturnHome],
+            trigger: Trigger::Arrived,
+            to: AiState::Idle,
+        },
+    ],
+};
+
+// wanders about and never gets involved
+pub const TOWNSFOLK: StateGraph = StateGraph {
+    start: AiState::Wander,
+    transitions: &[],
+};
+
+// sticks with its leader and fights whatever it sees
+pub const COMPANION: StateGraph = StateGraph {
+    start: AiState::Follow,
+    transitions: &[
+        Transition {
+            from: &[AiState::Follow],
+            trigger: Trigger::SeesTarget,
+            to: AiState::Chase,
+        },
+        Transition {
+            from: &[AiState::Chase],
+            trigger: Trigger::NoTarget,
+            to: AiState::Follow,
+        },
+    ],
+};
@@ -0,0 +1,29 @@
+use std::time::Instant;
+
+// how long it takes something thrown or shot to cross one cell
+const STEP_MILLIS: u128 = 30;
+
+// something flying along a line, the hit has already happened by the time it
+// is drawn so this is only for show
+pub struct Projectile {
+    pub path: Vec<(isize, isize)>,
+    pub glyph: char,
+    started: Instant,
+}
+
+impl Projectile {
+    pub fn new(path: Vec<(isize, isize)>, glyph: char) -> Self {
+        Self {
+            path,
+            glyph,
+            started: Instant::now(),
+        }
+    }
+
+    // the cell it is in right now, none once it has landed
+    pub fn position(&self) -> Option<(isize, isize)> {
+        let step = self.started.elapsed().as_millis() / STEP_MILLIS;
+
+        self.path.get(step as usize).copied()
+    }
+}
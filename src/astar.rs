@@ -1,6 +1,13 @@
-use std::{cell::RefCell, error::Error, rc::Rc};
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashSet},
+};
 
-use crate::{components::EntitySize, game_map::GameMap};
+use crate::{
+    components::Movement,
+    game_map::GameMap,
+    movement::{can_enter, in_bounds, step_cost},
+};
 
 const ADJACENT_SQUARES: [(isize, isize); 8] = [
     (0, -1),
@@ -13,180 +20,459 @@ const ADJACENT_SQUARES: [(isize, isize); 8] = [
     (1, 1),
 ];
 
-#[derive(Debug)]
-struct Node {
-    parent: Option<Rc<RefCell<Node>>>,
-    position: (isize, isize),
-    g: isize,
-    h: isize,
-    f: isize,
+// marks a cell with no parent in the came_from array
+const NO_PARENT: usize = usize::MAX;
+
+// an entry in the open list, the heap is a max heap so the ordering is
+// flipped to pop the lowest f first
+#[derive(Debug, PartialEq, Eq)]
+struct OpenNode {
+    f: usize,
+    g: usize,
+    index: usize,
 }
 
-impl Node {
-    fn new(
-        parent: Option<Rc<RefCell<Node>>>,
-        position: (isize, isize),
-    ) -> Self {
+impl Ord for OpenNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .f
+            .cmp(&self.f)
+            // on a tie prefer the node that is further along
+            .then_with(|| self.g.cmp(&other.g))
+            .then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// no step costs less then one including diagonals so the chebyshev distance
+// never over estimates
+fn heuristic(from: (isize, isize), to: (isize, isize)) -> usize {
+    let dx = (from.0 - to.0).abs();
+    let dy = (from.1 - to.1).abs();
+
+    dx.max(dy) as usize
+}
+
+// an inclusive rectangle of cells the search is allowed to use
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub x1: isize,
+    pub y1: isize,
+    pub x2: isize,
+    pub y2: isize,
+}
+
+impl Bounds {
+    pub fn whole_map(game_map: &GameMap) -> Self {
         Self {
-            parent,
-            position,
-            g: 0,
-            h: 0,
-            f: 0,
+            x1: 0,
+            y1: 0,
+            x2: game_map.map_info.column_count as isize - 1,
+            y2: game_map.map_info.row_count as isize - 1,
         }
     }
-}
 
-impl PartialEq for Node {
-    fn eq(&self, other: &Self) -> bool {
-        self.position == other.position
+    pub fn contains(&self, pos: (isize, isize)) -> bool {
+        pos.0 >= self.x1
+            && pos.0 <= self.x2
+            && pos.1 >= self.y1
+            && pos.1 <= self.y2
+    }
+
+    fn width(&self) -> isize {
+        self.x2 - self.x1 + 1
+    }
+
+    fn height(&self) -> isize {
+        self.y2 - self.y1 + 1
+    }
+
+    // the index in to the search arrays, not the map
+    fn local_index(&self, pos: (isize, isize)) -> usize {
+        ((pos.0 - self.x1) + (self.width() * (pos.1 - self.y1))) as usize
+    }
+
+    fn local_pos(&self, index: usize) -> (isize, isize) {
+        let index = index as isize;
+
+        (
+            self.x1 + (index % self.width()),
+            self.y1 + (index / self.width()),
+        )
     }
 }
 
-fn make_path(start_node: Rc<RefCell<Node>>) -> Vec<(isize, isize)> {
+fn make_path(
+    came_from: &[usize],
+    end_index: usize,
+    bounds: &Bounds,
+) -> Vec<(isize, isize)> {
     let mut path = vec![];
 
-    let mut current = Some(start_node);
+    let mut current = end_index;
 
-    while let Some(in_curr) = current {
-        path.push(in_curr.borrow().position);
+    while current != NO_PARENT {
+        path.push(bounds.local_pos(current));
 
-        if let Some(parent) = in_curr.borrow().parent.as_ref() {
-            current = Some(parent.clone());
-        } else {
-            current = None;
-        }
+        current = came_from[current];
     }
 
-    return path;
+    path.reverse();
+
+    path
 }
 
-// this is bad lol
+// find a path from start to end, the path includes both the start and the end
+// so the next step to take is the second item. the end is allowed to be
+// occupied since that is usually who we are going after
 pub fn astar(
     game_map: &GameMap,
     start: (isize, isize),
     end: (isize, isize),
-) -> Result<Option<Vec<(isize, isize)>>, Box<dyn Error>> {
-    let max_iter = game_map.map_info.total_count / 2;
+    movement: &Movement,
+    occupied: &HashSet<usize>,
+) -> Option<Vec<(isize, isize)>> {
+    let bounds = Bounds::whole_map(game_map);
 
-    let map_len = game_map.render_map.len() as isize;
+    astar_within(game_map, start, end, movement, occupied, &bounds)
+        .map(|(path, _)| path)
+}
 
-    let start_node = Node::new(None, start);
-    let start_node = Rc::new(RefCell::new(start_node));
+// the same as astar but the path has to stay inside of bounds, this also gives
+// back what the path costs
+pub fn astar_within(
+    game_map: &GameMap,
+    start: (isize, isize),
+    end: (isize, isize),
+    movement: &Movement,
+    occupied: &HashSet<usize>,
+    bounds: &Bounds,
+) -> Option<(Vec<(isize, isize)>, usize)> {
+    let column_count = game_map.map_info.column_count as isize;
+
+    if !in_bounds(game_map, start)
+        || !in_bounds(game_map, end)
+        || !bounds.contains(start)
+        || !bounds.contains(end)
+    {
+        return None;
+    }
 
-    let end_node = Node::new(None, end);
-    let end_node = Rc::new(RefCell::new(end_node));
+    let to_map_index =
+        |pos: (isize, isize)| (pos.0 + (column_count * pos.1)) as usize;
 
-    let mut open_list: Vec<Rc<RefCell<Node>>> = vec![];
+    if !can_enter(game_map, movement, to_map_index(end)) {
+        return None;
+    }
 
-    open_list.push(start_node);
+    let total_count = (bounds.width() * bounds.height()) as usize;
 
-    let mut closed_list: Vec<Rc<RefCell<Node>>> = vec![];
+    let start_index = bounds.local_index(start);
+    let end_index = bounds.local_index(end);
 
-    let mut outer_i = 0;
-    // loop till we find the end
-    while open_list.len() > 0 {
-        outer_i += 1;
+    let mut g_score = vec![usize::MAX; total_count];
+    let mut came_from = vec![NO_PARENT; total_count];
+    let mut closed = vec![false; total_count];
 
-        if outer_i > max_iter {
-            return Ok(None);
-        }
+    let mut open_list = BinaryHeap::new();
 
-        // get the item
-        let item = open_list.first().unwrap();
-        let current_pos = item.borrow().position;
-        let mut current_f = item.borrow().f;
-        let mut index = 0;
-        // if we fond the end
-        if current_pos == end_node.borrow().position {
-            return Ok(Some(make_path(item.clone())));
-        }
+    g_score[start_index] = 0;
 
-        for (i, node) in open_list.iter().enumerate() {
-            if current_f > node.borrow().f {
-                index = i;
+    open_list.push(OpenNode {
+        f: heuristic(start, end),
+        g: 0,
+        index: start_index,
+    });
 
-                current_f = node.borrow().f;
-            }
+    while let Some(OpenNode { g, index, .. }) = open_list.pop() {
+        if index == end_index {
+            return Some((make_path(&came_from, end_index, bounds), g));
         }
 
-        let item = open_list.remove(index);
-        let current_pos = item.borrow().position;
-
-        // if we fond the end
-        if current_pos.0 == end_node.borrow().position.0
-            && current_pos.1 == end_node.borrow().position.1
-        {
-            return Ok(Some(make_path(item)));
+        // a cell can be pushed more then once, only the first pop counts
+        if closed[index] {
+            continue;
         }
 
-        // add current_node to closed_list
-        closed_list.push(item.clone());
+        closed[index] = true;
 
-        // generate children
-        let mut children: Vec<Rc<RefCell<Node>>> = vec![];
+        let current_pos = bounds.local_pos(index);
 
-        // make potential children
-        for new_pos in ADJACENT_SQUARES.iter() {
-            let node_position: (isize, isize) =
-                (current_pos.0 + new_pos.0, current_pos.1 + new_pos.1);
+        for adjust in ADJACENT_SQUARES.iter() {
+            let node_position =
+                (current_pos.0 + adjust.0, current_pos.1 + adjust.1);
 
-            if node_position.0 < 0
-                || node_position.0 > map_len
-                || node_position.1 < 0
-                || node_position.1 > map_len
-            {
+            if !bounds.contains(node_position) {
                 continue;
             }
 
-            let node_index = node_position.0
-                + (game_map.map_info.column_count as isize * node_position.1);
-
             // check if can move
-            if game_map.render_map[node_index as usize].ent_size
-                < EntitySize::Medium
-                || node_position == end_node.borrow().position
+            let mut cost = if let Some(cost) =
+                step_cost(game_map, movement, current_pos, node_position)
             {
-                let new_node = Rc::new(RefCell::new(Node::new(
-                    Some(item.clone()),
-                    node_position,
-                )));
+                cost
+            } else {
+                continue;
+            };
 
-                children.push(new_node);
+            let node_index = bounds.local_index(node_position);
+
+            if closed[node_index] {
+                continue;
+            }
+
+            if node_index != end_index
+                && occupied.contains(&to_map_index(node_position))
+            {
+                match movement.occupied_cost {
+                    Some(extra) => cost += extra,
+                    None => continue,
+                }
+            }
+
+            let new_g = g + cost;
+
+            if new_g < g_score[node_index] {
+                g_score[node_index] = new_g;
+                came_from[node_index] = index;
+
+                open_list.push(OpenNode {
+                    f: new_g + heuristic(node_position, end),
+                    g: new_g,
+                    index: node_index,
+                });
             }
         }
+    }
+
+    // we looked at everything we could reach and the end was not there
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_map::test_map;
+
+    // a walled in room with one gap in the top wall and a pond in the middle
+    const MAP: [&str; 7] = [
+        "..........",
+        ".#####.##.",
+        ".#.....#..",
+        ".#.~~~.#..",
+        ".#.....#..",
+        ".#######..",
+        "..........",
+    ];
+
+    // the cheapest cost from start to every cell the slow way, to check the
+    // search against
+    fn reference_costs(
+        game_map: &GameMap,
+        start: (isize, isize),
+        movement: &Movement,
+    ) -> Vec<usize> {
+        let column_count = game_map.map_info.column_count as isize;
+        let index =
+            |pos: (isize, isize)| (pos.0 + column_count * pos.1) as usize;
+
+        let mut costs = vec![usize::MAX; game_map.render_map.len()];
+
+        costs[index(start)] = 0;
+
+        let mut changed = true;
+
+        while changed {
+            changed = false;
+
+            for i in 0..costs.len() {
+                if costs[i] == usize::MAX {
+                    continue;
+                }
+
+                let from =
+                    (i as isize % column_count, i as isize / column_count);
 
-        // add children with filter to open list
-        'children: for child in children {
-            let mut child_inner = child.borrow_mut();
+                for adjust in ADJACENT_SQUARES.iter() {
+                    let to = (from.0 + adjust.0, from.1 + adjust.1);
 
-            for cc in &closed_list {
-                if child_inner.position == cc.borrow().position {
-                    continue 'children;
+                    if let Some(cost) = step_cost(game_map, movement, from, to)
+                    {
+                        if costs[i] + cost < costs[index(to)] {
+                            costs[index(to)] = costs[i] + cost;
+                            changed = true;
+                        }
+                    }
                 }
             }
+        }
 
-            child_inner.g = item.borrow().g + 1;
+        costs
+    }
 
-            child_inner.h =
-                (child_inner.position.0 - end_node.borrow().position.0).pow(2)
-                    + (child_inner.position.1 - end_node.borrow().position.1)
-                        .pow(2);
+    fn path_cost(
+        game_map: &GameMap,
+        path: &[(isize, isize)],
+        movement: &Movement,
+    ) -> usize {
+        path.windows(2)
+            .map(|step| {
+                assert!(heuristic(step[0], step[1]) == 1, "{:?}", step);
+
+                step_cost(game_map, movement, step[0], step[1])
+                    .expect("the path goes through something it cant enter")
+            })
+            .sum()
+    }
 
-            child_inner.f = child_inner.g + child_inner.h;
+    #[test]
+    fn no_path_to_a_closed_off_goal() {
+        let game_map = test_map(&[".....", ".###.", ".#.#.", ".###.", "....."]);
+
+        let movement = Movement::default();
+
+        assert_eq!(
+            astar(&game_map, (0, 0), (2, 2), &movement, &HashSet::new()),
+            None
+        );
+
+        // a wall can not be the goal either
+        assert_eq!(
+            astar(&game_map, (0, 0), (1, 1), &movement, &HashSet::new()),
+            None
+        );
+    }
 
-            for o_node in &open_list {
-                if child_inner.position == o_node.borrow().position
-                    && child_inner.g > o_node.borrow().g
-                {
-                    continue 'children;
+    #[test]
+    fn finds_the_cheapest_path_around_obstacles() {
+        let game_map = test_map(&MAP);
+        let movement = Movement::default();
+
+        let start = (4, 2);
+        let costs = reference_costs(&game_map, start, &movement);
+
+        let column_count = game_map.map_info.column_count as isize;
+
+        for (i, expected) in costs.iter().enumerate() {
+            let end = (i as isize % column_count, i as isize / column_count);
+
+            let found = astar_within(
+                &game_map,
+                start,
+                end,
+                &movement,
+                &HashSet::new(),
+                &Bounds::whole_map(&game_map),
+            );
+
+            match found {
+                Some((path, cost)) => {
+                    assert_eq!(cost, *expected, "to {:?}", end);
+                    assert_eq!(path.first(), Some(&start));
+                    assert_eq!(path.last(), Some(&end));
+                    assert_eq!(path_cost(&game_map, &path, &movement), cost);
                 }
+                None => assert_eq!(*expected, usize::MAX, "to {:?}", end),
             }
-
-            open_list.push(child.clone());
         }
+
+        // out through the gap and round the wall
+        let (_, cost) = astar_within(
+            &game_map,
+            start,
+            (4, 6),
+            &movement,
+            &HashSet::new(),
+            &Bounds::whole_map(&game_map),
+        )
+        .unwrap();
+
+        assert_eq!(cost, costs[(4 + column_count * 6) as usize]);
+        assert!(cost > 3);
+    }
+
+    #[test]
+    fn occupied_cells_are_avoided_unless_they_are_the_goal() {
+        let game_map = test_map(&["...", "...", "..."]);
+        let movement = Movement::default();
+
+        let occupied: HashSet<usize> = [1, 4, 7].iter().copied().collect();
+
+        assert_eq!(
+            astar(&game_map, (0, 0), (2, 0), &movement, &occupied),
+            None
+        );
+
+        assert!(
+            astar(&game_map, (0, 0), (1, 1), &movement, &occupied).is_some()
+        );
     }
 
-    // i guess we get her if we scan everything and cant find the target?
-    return Err(Box::from("we should not get here"));
+    #[test]
+    fn astar_within_stays_inside_its_bounds() {
+        let game_map = test_map(&MAP);
+        let movement = Movement::default();
+
+        // the inside of the room, the only way out is through the gap
+        let room = Bounds {
+            x1: 2,
+            y1: 2,
+            x2: 6,
+            y2: 4,
+        };
+
+        let (path, _) = astar_within(
+            &game_map,
+            (2, 2),
+            (6, 4),
+            &movement,
+            &HashSet::new(),
+            &room,
+        )
+        .unwrap();
+
+        assert!(path.iter().all(|pos| room.contains(*pos)));
+
+        // getting out of the room means going through the gap at the top,
+        // which is outside of these
+        let below = Bounds {
+            x1: 0,
+            y1: 2,
+            x2: 9,
+            y2: 6,
+        };
+
+        assert!(astar(&game_map, (4, 2), (4, 6), &movement, &HashSet::new())
+            .is_some());
+
+        assert_eq!(
+            astar_within(
+                &game_map,
+                (4, 2),
+                (4, 6),
+                &movement,
+                &HashSet::new(),
+                &below,
+            ),
+            None
+        );
+
+        // and an end outside of them is never found
+        assert_eq!(
+            astar_within(
+                &game_map,
+                (2, 2),
+                (0, 0),
+                &movement,
+                &HashSet::new(),
+                &room,
+            ),
+            None
+        );
+    }
 }
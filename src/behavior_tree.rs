@@ -0,0 +1,375 @@
+// behavior trees for monsters that need more then a state graph, they are
+// read from the .bt files in assets/ai so they can be changed without
+// touching the code.
+//
+// every line is one node and the lines under it that are indented further
+// are its children, anything after a # is ignored
+//
+//   selector        runs its children in order until one succeeds
+//   sequence        runs its children in order until one fails
+//   invert          turns success in to failure and the other way round
+//   cooldown N      runs its child at most once every N turns
+//   chance N        runs its child one time in N
+//
+// the leaves either check something or try to do something
+//
+//   sees-target, hurt, target-within N, clear-shot, at-home
+//   chase, flee, attack, shoot, wander, go-home, search, follow, idle,
+//   use-item NAME
+
+use std::{collections::HashMap, error::Error, fs, path::Path, rc::Rc};
+
+#[derive(Debug)]
+pub enum Leaf {
+    SeesTarget,
+    // down to a quarter of its health
+    Hurt,
+    TargetWithin(isize),
+    ClearShot,
+    AtHome,
+    Chase,
+    Flee,
+    // a melee attack on a target that is next to it
+    Attack,
+    Shoot,
+    Wander,
+    GoHome,
+    // go to where the target was last seen and look around, fails once it
+    // has been forgotten
+    Search,
+    // stay close to whoever it follows
+    Follow,
+    // always succeeds without doing anything
+    Idle,
+    // use the first item in the inventory with this name
+    UseItem(String),
+}
+
+#[derive(Debug)]
+pub enum Node {
+    Sequence(Vec<Node>),
+    Selector(Vec<Node>),
+    Invert(Box<Node>),
+    // the id is unique in the tree so each ai can remember when it can run
+    // the child again
+    Cooldown {
+        id: usize,
+        turns: usize,
+        child: Box<Node>,
+    },
+    Chance {
+        one_in: u32,
+        child: Box<Node>,
+    },
+    Leaf(Leaf),
+}
+
+struct Line<'a> {
+    number: usize,
+    indent: usize,
+    words: Vec<&'a str>,
+}
+
+fn number<T: std::str::FromStr>(line: &Line) -> Result<T, String> {
+    line.words
+        .get(1)
+        .and_then(|word| word.parse().ok())
+        .ok_or_else(|| {
+            format!("line {}: {} needs a number", line.number, line.words[0])
+        })
+}
+
+fn parse_leaf(line: &Line) -> Result<Leaf, String> {
+    let leaf = match line.words[0] {
+        "sees-target" => Leaf::SeesTarget,
+        "hurt" => Leaf::Hurt,
+        "target-within" => Leaf::TargetWithin(number(line)?),
+        "clear-shot" => Leaf::ClearShot,
+        "at-home" => Leaf::AtHome,
+        "chase" => Leaf::Chase,
+        "flee" => Leaf::Flee,
+        "attack" => Leaf::Attack,
+        "shoot" => Leaf::Shoot,
+        "wander" => Leaf::Wander,
+        "go-home" => Leaf::GoHome,
+        "search" => Leaf::Search,
+        "follow" => Leaf::Follow,
+        "idle" => Leaf::Idle,
+        "use-item" if line.words.len() > 1 => {
+            Leaf::UseItem(line.words[1..].join(" "))
+        }
+        word => {
+            return Err(format!("line {}: unknown node {}", line.number, word))
+        }
+    };
+
+    Ok(leaf)
+}
+
+// parse the node at lines[*at] and everything under it
+fn parse_node(
+    lines: &[Line],
+    at: &mut usize,
+    next_id: &mut usize,
+) -> Result<Node, String> {
+    let line = &lines[*at];
+
+    *at += 1;
+
+    let mut children = vec![];
+
+    // every child has to line up with the first one
+    let child_indent = lines.get(*at).map(|child| child.indent);
+
+    while *at < lines.len() && lines[*at].indent > line.indent {
+        if Some(lines[*at].indent) != child_indent {
+            return Err(format!(
+                "line {}: does not line up with the lines above it",
+                lines[*at].number
+            ));
+        }
+
+        children.push(parse_node(lines, at, next_id)?);
+    }
+
+    let one_child = |mut children: Vec<Node>| {
+        if children.len() == 1 {
+            Ok(Box::new(children.remove(0)))
+        } else {
+            Err(format!(
+                "line {}: {} needs exactly one child",
+                line.number, line.words[0]
+            ))
+        }
+    };
+
+    let node = match line.words[0] {
+        "sequence" | "selector" if children.is_empty() => {
+            return Err(format!(
+                "line {}: {} needs children",
+                line.number, line.words[0]
+            ));
+        }
+        "sequence" => Node::Sequence(children),
+        "selector" => Node::Selector(children),
+        "invert" => Node::Invert(one_child(children)?),
+        "cooldown" => {
+            *next_id += 1;
+
+            Node::Cooldown {
+                id: *next_id,
+                turns: number(line)?,
+                child: one_child(children)?,
+            }
+        }
+        "chance" => {
+            let one_in = number(line)?;
+
+            if one_in == 0 {
+                return Err(format!(
+                    "line {}: chance can not be 0",
+                    line.number
+                ));
+            }
+
+            Node::Chance {
+                one_in,
+                child: one_child(children)?,
+            }
+        }
+        _ if !children.is_empty() => {
+            return Err(format!(
+                "line {}: {} can not have children",
+                line.number, line.words[0]
+            ));
+        }
+        _ => Node::Leaf(parse_leaf(line)?),
+    };
+
+    Ok(node)
+}
+
+pub fn parse_tree(text: &str) -> Result<Node, String> {
+    let lines: Vec<Line> = text
+        .lines()
+        .enumerate()
+        .filter_map(|(i, text)| {
+            let text = text.split('#').next().unwrap_or("");
+
+            let words: Vec<&str> = text.split_whitespace().collect();
+
+            if words.is_empty() {
+                return None;
+            }
+
+            Some(Line {
+                number: i + 1,
+                indent: text.len() - text.trim_start().len(),
+                words,
+            })
+        })
+        .collect();
+
+    if lines.is_empty() {
+        return Err(String::from("the tree is empty"));
+    }
+
+    let mut at = 0;
+    let mut next_id = 0;
+
+    let root = parse_node(&lines, &mut at, &mut next_id)?;
+
+    if at < lines.len() {
+        return Err(format!(
+            "line {}: a tree can only have one root",
+            lines[at].number
+        ));
+    }
+
+    Ok(root)
+}
+
+// every tree in a directory by the name of its file
+pub fn load_trees(
+    dir: &Path,
+) -> Result<HashMap<String, Rc<Node>>, Box<dyn Error>> {
+    let mut trees = HashMap::new();
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        if path.extension().is_none_or(|ext| ext != "bt") {
+            continue;
+        }
+
+        let name = if let Some(name) = path.file_stem() {
+            name.to_string_lossy().to_string()
+        } else {
+            continue;
+        };
+
+        let tree = parse_tree(&fs::read_to_string(&path)?)
+            .map_err(|err| format!("{}: {}", path.display(), err))?;
+
+        trees.insert(name, Rc::new(tree));
+    }
+
+    Ok(trees)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(text: &str) -> String {
+        parse_tree(text).unwrap_err()
+    }
+
+    #[test]
+    fn parses_a_tree() {
+        let tree = parse_tree(
+            "# a comment on its own line
+selector
+    sequence   # and one after a node
+        target-within 2
+        attack
+
+    cooldown 5
+        chance 3
+            use-item scroll of summoning
+    invert
+        hurt
+",
+        )
+        .unwrap();
+
+        let children = match tree {
+            Node::Selector(children) => children,
+            node => panic!("{:?}", node),
+        };
+
+        assert_eq!(children.len(), 3);
+
+        match &children[0] {
+            Node::Sequence(leaves) => {
+                assert!(matches!(leaves[0], Node::Leaf(Leaf::TargetWithin(2))));
+                assert!(matches!(leaves[1], Node::Leaf(Leaf::Attack)));
+            }
+            node => panic!("{:?}", node),
+        }
+
+        match &children[1] {
+            Node::Cooldown { id, turns, child } => {
+                assert_eq!((*id, *turns), (1, 5));
+
+                match &**child {
+                    Node::Chance { one_in: 3, child } => match &**child {
+                        Node::Leaf(Leaf::UseItem(name)) => {
+                            assert_eq!(name, "scroll of summoning")
+                        }
+                        node => panic!("{:?}", node),
+                    },
+                    node => panic!("{:?}", node),
+                }
+            }
+            node => panic!("{:?}", node),
+        }
+
+        assert!(matches!(children[2], Node::Invert(_)));
+    }
+
+    #[test]
+    fn the_trees_in_the_assets_load() {
+        let trees = load_trees(Path::new("assets/ai")).unwrap();
+
+        assert!(trees.contains_key("necromancer"));
+    }
+
+    #[test]
+    fn empty_trees_are_rejected() {
+        assert_eq!(error(""), "the tree is empty");
+        assert_eq!(error("  # nothing but a comment\n\n"), "the tree is empty");
+    }
+
+    #[test]
+    fn unknown_nodes_are_rejected() {
+        assert_eq!(
+            error("selector\n    chase\n    dance\n"),
+            "line 3: unknown node dance"
+        );
+        assert_eq!(error("use-item\n"), "line 1: unknown node use-item");
+    }
+
+    #[test]
+    fn bad_indentation_is_rejected() {
+        assert_eq!(
+            error("selector\n    chase\n  flee\n"),
+            "line 3: does not line up with the lines above it"
+        );
+        assert_eq!(
+            error("selector\n    chase\nflee\n"),
+            "line 3: a tree can only have one root"
+        );
+        assert_eq!(
+            error("chase\n    flee\n"),
+            "line 1: chase can not have children"
+        );
+    }
+
+    #[test]
+    fn bad_arguments_and_children_are_rejected() {
+        assert_eq!(
+            error("target-within far\n"),
+            "line 1: target-within needs a number"
+        );
+        assert_eq!(
+            error("chance 0\n    idle\n"),
+            "line 1: chance can not be 0"
+        );
+        assert_eq!(
+            error("invert\n    idle\n    chase\n"),
+            "line 1: invert needs exactly one child"
+        );
+        assert_eq!(error("sequence\n"), "line 1: sequence needs children");
+    }
+}
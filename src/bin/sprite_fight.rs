@@ -1,7 +1,17 @@
-use sprite_fight::{config::make_game_info, run_game};
+use sprite_fight::{
+    config::{game_mode, make_game_info, net_role, versus},
+    run_game,
+};
 
 fn main() -> Result<(), Box<dyn ::std::error::Error>> {
     let game_info = make_game_info();
 
-    run_game(game_info.0, game_info.1, game_info.2)
+    run_game(
+        game_info.0,
+        game_info.1,
+        game_info.2,
+        game_mode(),
+        versus(),
+        net_role()?,
+    )
 }
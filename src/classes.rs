@@ -0,0 +1,92 @@
+// the starting classes the player picks from and the abilities they can learn
+
+use crate::{components::StatusKind, consumables::Effect};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Ability {
+    SecondWind,
+    Sprint,
+    Blink,
+}
+
+pub struct AbilityDef {
+    pub ability: Ability,
+    pub name: &'static str,
+    pub effect: Effect,
+    // how many turns before it can be used again
+    pub cooldown: usize,
+}
+
+pub const ABILITIES: [AbilityDef; 3] = [
+    AbilityDef {
+        ability: Ability::SecondWind,
+        name: "second wind",
+        effect: Effect::Heal(8),
+        cooldown: 30,
+    },
+    AbilityDef {
+        ability: Ability::Sprint,
+        name: "sprint",
+        effect: Effect::Status {
+            kind: StatusKind::Haste,
+            turns: 8,
+            potency: 0,
+        },
+        cooldown: 25,
+    },
+    AbilityDef {
+        ability: Ability::Blink,
+        name: "blink",
+        effect: Effect::Teleport,
+        cooldown: 40,
+    },
+];
+
+pub fn ability_def(ability: Ability) -> &'static AbilityDef {
+    ABILITIES.iter().find(|def| def.ability == ability).unwrap()
+}
+
+pub struct ClassDef {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub health: isize,
+    pub attack: isize,
+    pub defense: isize,
+    pub vision: isize,
+    // the map glyphs of the equipment the class starts with, it is all worn
+    pub kit: &'static [char],
+    pub ability: Ability,
+}
+
+pub const CLASSES: [ClassDef; 3] = [
+    ClassDef {
+        name: "warrior",
+        description: "tough and hits hard up close",
+        health: 14,
+        attack: 3,
+        defense: 2,
+        vision: 5,
+        kit: &[')', '['],
+        ability: Ability::SecondWind,
+    },
+    ClassDef {
+        name: "ranger",
+        description: "shoots from a distance",
+        health: 10,
+        attack: 2,
+        defense: 1,
+        vision: 6,
+        kit: &['}'],
+        ability: Ability::Sprint,
+    },
+    ClassDef {
+        name: "rogue",
+        description: "sees far and slips away",
+        health: 9,
+        attack: 3,
+        defense: 1,
+        vision: 7,
+        kit: &['='],
+        ability: Ability::Blink,
+    },
+];
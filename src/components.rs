@@ -1,4 +1,12 @@
-use std::collections::HashMap;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, VecDeque},
+    hash::BuildHasherDefault,
+};
+
+use crate::{
+    ai_states::{AiState, StateGraph},
+    classes::Ability,
+};
 
 #[derive(Debug, Clone, Copy, PartialOrd, PartialEq)]
 pub enum EntitySize {
@@ -20,12 +28,103 @@ pub struct Selected {
     pub entity: Option<usize>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum AiType {
     Basic,
+    // keeps its distance and shoots
+    Archer,
 }
 
 pub struct Ai {
     pub ai_type: AiType,
+    pub graph: &'static StateGraph,
+    pub state: AiState,
+    // how many turns it has been in the state
+    pub state_turns: usize,
+    // the index of the cell it was spawned in
+    pub home: usize,
+    // the cells it walks between on patrol, made the first time it patrols
+    pub patrol: Vec<(isize, isize)>,
+    pub waypoint: usize,
+    // who it stays close to when it follows
+    pub leader: Option<usize>,
+    // the name of a behavior tree to use instead of the graph
+    pub tree: Option<String>,
+    // the turn each cooldown node in the tree can run again
+    pub tree_ready: HashMap<usize, usize>,
+}
+
+impl Ai {
+    pub fn new(
+        ai_type: AiType,
+        graph: &'static StateGraph,
+        home: usize,
+    ) -> Self {
+        Self {
+            ai_type,
+            graph,
+            state: graph.start,
+            state_turns: 0,
+            home,
+            patrol: Vec::new(),
+            waypoint: 0,
+            leader: None,
+            tree: None,
+            tree_ready: HashMap::new(),
+        }
+    }
+}
+
+// a path the ai is following so it dose not have to find a new one every turn
+pub struct AiPath {
+    pub steps: VecDeque<(isize, isize)>,
+    // where the target was when the path was made
+    pub goal: (isize, isize),
+}
+
+// where an ai last saw its target and for how long it keeps it in mind
+pub struct Memory {
+    pub last_seen: Option<(isize, isize)>,
+    // the turn it was last seen on
+    pub seen_at: usize,
+    // how many turns after that it gives up
+    pub forget_after: usize,
+    // the cell it is walking to while it searches, it starts off as where
+    // the target was seen
+    pub search_spot: Option<(isize, isize)>,
+}
+
+impl Memory {
+    pub fn new(forget_after: usize) -> Self {
+        Self {
+            last_seen: None,
+            seen_at: 0,
+            forget_after,
+            search_spot: None,
+        }
+    }
+}
+
+// monsters in the same pack share what they see and fight together, strength
+// is how many there were to start with
+pub struct Pack {
+    pub id: usize,
+    pub strength: usize,
+}
+
+// moving slower and quieter
+pub struct Sneaking;
+
+// how fast an entity is before anything else changes it, 100 is normal and
+// entitys without one are normal
+pub struct Speed {
+    pub value: isize,
+}
+
+// what the entity has saved up towards its next action, it gains its speed
+// every turn and acts once it has enough
+pub struct Energy {
+    pub value: isize,
 }
 
 pub struct Name {
@@ -37,22 +136,299 @@ pub struct Health {
     pub cur_value: isize,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MovementMode {
+    Walk,
+    Fly,
+    Swim,
+    Phase,
+}
+
+// how an entity gets around, entitys without one walk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Movement {
+    pub mode: MovementMode,
+    // can a diagonal step squeeze past a blocked corner
+    pub cut_corners: bool,
+    // the extra cost to path through a cell someone is standing in, none
+    // means the cell can not be used at all
+    pub occupied_cost: Option<usize>,
+}
+
+impl Default for Movement {
+    fn default() -> Self {
+        Self {
+            mode: MovementMode::Walk,
+            cut_corners: true,
+            occupied_cost: None,
+        }
+    }
+}
+
+// a light that is carried by or attached to an entity, the color is plain rgb
+// and flicker is how much of the intensity can randomly drop each turn
+pub struct LightSource {
+    pub radius: isize,
+    pub color: (u8, u8, u8),
+    pub intensity: f64,
+    pub flicker: f64,
+}
+
+pub struct Attack {
+    pub power: isize,
+}
+
+pub struct Defense {
+    pub armor: isize,
+}
+
+// something that can hit from a distance, a bow being carried or a monster
+// that shoots on its own
+#[derive(Debug, Clone, Copy)]
+pub struct Ranged {
+    pub power: isize,
+    pub range: isize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ItemKind {
+    // the index of what it is in the consumables table
+    Consumable(usize),
+    Equipment,
+}
+
+// something that can be picked up, items on the floor have a render and items
+// being carried dont
+pub struct Item {
+    pub kind: ItemKind,
+    pub glyph: char,
+}
+
+// a pile of the same item that takes up one inventory slot
+pub struct Stack {
+    pub count: usize,
+    pub max: usize,
+}
+
+// the uses left in something like a wand, it is gone once they run out
+pub struct Charges {
+    pub count: usize,
+}
+
+pub struct Inventory {
+    pub items: Vec<usize>,
+    pub capacity: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EquipSlot {
+    Weapon,
+    Armor,
+    Ring,
+}
+
+// how much wearing something changes each stat
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StatBonus {
+    pub attack: isize,
+    pub defense: isize,
+    pub vision: isize,
+    pub speed: isize,
+}
+
+pub struct Equippable {
+    pub slot: EquipSlot,
+    pub bonus: StatBonus,
+}
+
+// the item in each slot, the items are still kept in the inventory
+pub struct Equipment {
+    pub slots: HashMap<EquipSlot, usize>,
+}
+
+// how many cells away an entity can see
+pub struct Vision {
+    pub radius: isize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StatusKind {
+    Poison,
+    Regeneration,
+    Haste,
+    Slow,
+    Confusion,
+    Blindness,
+    Paralysis,
+    Invisibility,
+}
+
+// something that lasts a number of turns, potency is how strong it is for the
+// effects that have a strength like poison damage
+#[derive(Debug, Clone, Copy)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub turns: usize,
+    pub potency: isize,
+}
+
+// everything affecting an entity right now, there is only ever one of each
+// kind
+pub struct StatusEffects {
+    pub effects: Vec<StatusEffect>,
+}
+
+// how far along the player is, xp is what has been earned towards the next
+// level and pending is how many level ups are still waiting on a choice
+pub struct Experience {
+    pub level: usize,
+    pub xp: usize,
+    pub pending: usize,
+}
+
+// how much xp killing something is worth
+pub struct XpReward {
+    pub value: usize,
+}
+
+// the index of the class in the classes table
+pub struct Class {
+    pub index: usize,
+}
+
+// abilities that have been learned and how many turns until each can be used
+// again, abilities that are ready have no cooldown
+pub struct Abilities {
+    pub known: Vec<Ability>,
+    pub cooldowns: HashMap<Ability, usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FactionKind {
+    Player,
+    Undead,
+    Beasts,
+    Town,
+    // the other players in a versus game, each number is a side of its own
+    Rival(usize),
+}
+
+// who an entity sides with, how factions get on is in the factions table
+pub struct Faction {
+    pub kind: FactionKind,
+}
+
+// the components are kept in maps with a fixed hasher, so going over one visits
+// the entitys in the same order every time the game is run. this is what lets
+// two machines play the same game in step
+pub type Store<T> = HashMap<usize, T, BuildHasherDefault<DefaultHasher>>;
+
 pub struct ComponentStore {
-    pub health: HashMap<usize, Health>,
-    pub render: HashMap<usize, Render>,
-    pub selected: HashMap<usize, Selected>,
-    pub name: HashMap<usize, Name>,
-    pub ai: HashMap<usize, Ai>,
+    pub health: Store<Health>,
+    pub render: Store<Render>,
+    pub selected: Store<Selected>,
+    pub name: Store<Name>,
+    pub ai: Store<Ai>,
+    pub light_source: Store<LightSource>,
+    pub movement: Store<Movement>,
+    pub ai_path: Store<AiPath>,
+    pub attack: Store<Attack>,
+    pub defense: Store<Defense>,
+    pub item: Store<Item>,
+    pub stack: Store<Stack>,
+    pub charges: Store<Charges>,
+    pub inventory: Store<Inventory>,
+    pub equippable: Store<Equippable>,
+    pub equipment: Store<Equipment>,
+    pub vision: Store<Vision>,
+    pub status_effects: Store<StatusEffects>,
+    pub experience: Store<Experience>,
+    pub xp_reward: Store<XpReward>,
+    pub class: Store<Class>,
+    pub abilities: Store<Abilities>,
+    pub ranged: Store<Ranged>,
+    pub faction: Store<Faction>,
+    pub memory: Store<Memory>,
+    pub pack: Store<Pack>,
+    pub sneaking: Store<Sneaking>,
+    pub speed: Store<Speed>,
+    pub energy: Store<Energy>,
 }
 
 impl Default for ComponentStore {
     fn default() -> Self {
         Self {
-            health: HashMap::new(),
-            render: HashMap::new(),
-            selected: HashMap::new(),
-            name: HashMap::new(),
-            ai: HashMap::new(),
+            health: Store::default(),
+            render: Store::default(),
+            selected: Store::default(),
+            name: Store::default(),
+            ai: Store::default(),
+            light_source: Store::default(),
+            movement: Store::default(),
+            ai_path: Store::default(),
+            attack: Store::default(),
+            defense: Store::default(),
+            item: Store::default(),
+            stack: Store::default(),
+            charges: Store::default(),
+            inventory: Store::default(),
+            equippable: Store::default(),
+            equipment: Store::default(),
+            vision: Store::default(),
+            status_effects: Store::default(),
+            experience: Store::default(),
+            xp_reward: Store::default(),
+            class: Store::default(),
+            abilities: Store::default(),
+            ranged: Store::default(),
+            faction: Store::default(),
+            memory: Store::default(),
+            pack: Store::default(),
+            sneaking: Store::default(),
+            speed: Store::default(),
+            energy: Store::default(),
         }
     }
 }
+
+impl ComponentStore {
+    // take every component away from an entity so it is gone from the game
+    pub fn remove_entity(&mut self, entity_id: usize) {
+        self.remove_all_but_body(entity_id);
+
+        self.render.remove(&entity_id);
+        self.name.remove(&entity_id);
+    }
+
+    // everything but its name and where it is drawn, this is what turns a
+    // creature in to a corpse. every new component has to go in here
+    pub fn remove_all_but_body(&mut self, entity_id: usize) {
+        self.health.remove(&entity_id);
+        self.selected.remove(&entity_id);
+        self.ai.remove(&entity_id);
+        self.light_source.remove(&entity_id);
+        self.movement.remove(&entity_id);
+        self.ai_path.remove(&entity_id);
+        self.attack.remove(&entity_id);
+        self.defense.remove(&entity_id);
+        self.item.remove(&entity_id);
+        self.stack.remove(&entity_id);
+        self.charges.remove(&entity_id);
+        self.inventory.remove(&entity_id);
+        self.equippable.remove(&entity_id);
+        self.equipment.remove(&entity_id);
+        self.vision.remove(&entity_id);
+        self.status_effects.remove(&entity_id);
+        self.experience.remove(&entity_id);
+        self.xp_reward.remove(&entity_id);
+        self.class.remove(&entity_id);
+        self.abilities.remove(&entity_id);
+        self.ranged.remove(&entity_id);
+        self.faction.remove(&entity_id);
+        self.memory.remove(&entity_id);
+        self.pack.remove(&entity_id);
+        self.sneaking.remove(&entity_id);
+        self.speed.remove(&entity_id);
+        self.energy.remove(&entity_id);
+    }
+}
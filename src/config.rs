@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::{env, path::PathBuf};
 
 use crate::{game_map::MapInfo, tileset::TileInfo, WindowInfo};
 
@@ -43,3 +43,113 @@ pub fn make_game_info() -> (WindowInfo, TileInfo, MapInfo) {
 
     (window_info, tile_info, map_info)
 }
+
+// how the game moves forward, a turn at a time or on its own at a fixed tick
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameMode {
+    Turns,
+    RealTime { tick_ms: u64 },
+}
+
+// how long a tick lasts in real time unless it is set with --tick-ms
+const DEFAULT_TICK_MS: u64 = 250;
+
+// turns unless --real-time is passed on the command line
+pub fn game_mode() -> GameMode {
+    let args: Vec<String> = env::args().collect();
+
+    if !args.iter().any(|arg| arg == "--real-time") {
+        return GameMode::Turns;
+    }
+
+    let tick_ms = args
+        .iter()
+        .position(|arg| arg == "--tick-ms")
+        .and_then(|i| args.get(i + 1))
+        .and_then(|ms| ms.parse().ok())
+        .unwrap_or(DEFAULT_TICK_MS);
+
+    GameMode::RealTime {
+        tick_ms: tick_ms.max(1),
+    }
+}
+
+// two players on one keyboard fighting each other, with --versus
+pub fn versus() -> bool {
+    env::args().any(|arg| arg == "--versus")
+}
+
+// which side of a networked game this machine is on
+#[derive(Debug, Clone, PartialEq)]
+pub enum NetRole {
+    Host { port: u16, players: usize },
+    Join { addr: String },
+}
+
+// --host PORT with --players N for how many to wait for, or --join ADDR. a
+// port or player count that can not be read is an error rather then quietly
+// starting a game on this machine alone
+pub fn net_role() -> Result<Option<NetRole>, String> {
+    let args: Vec<String> = env::args().collect();
+
+    parse_net_role(&args)
+}
+
+fn parse_net_role(args: &[String]) -> Result<Option<NetRole>, String> {
+    let value = |flag: &str| {
+        args.iter()
+            .position(|arg| arg == flag)
+            .map(|i| args.get(i + 1).cloned().unwrap_or_default())
+    };
+
+    if let Some(addr) = value("--join") {
+        return Ok(Some(NetRole::Join { addr }));
+    }
+
+    let port = if let Some(port) = value("--host") {
+        port
+    } else {
+        return Ok(None);
+    };
+
+    let port = port
+        .parse()
+        .map_err(|_| format!("--host needs a port, not {:?}", port))?;
+
+    let players = if let Some(players) = value("--players") {
+        players.parse::<usize>().map_err(|_| {
+            format!("--players needs a number, not {:?}", players)
+        })?
+    } else {
+        2
+    };
+
+    Ok(Some(NetRole::Host {
+        port,
+        players: players.max(2),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn bad_host_arguments_are_errors() {
+        assert_eq!(parse_net_role(&args("game")), Ok(None));
+        assert_eq!(
+            parse_net_role(&args("game --host 7777 --players 3")),
+            Ok(Some(NetRole::Host {
+                port: 7777,
+                players: 3
+            }))
+        );
+        assert!(parse_net_role(&args("game --host seven")).is_err());
+        assert!(parse_net_role(&args("game --host")).is_err());
+        assert!(parse_net_role(&args("game --host 7777 --players x")).is_err());
+    }
+}
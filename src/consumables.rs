@@ -0,0 +1,223 @@
+// every potion, scroll and wand in the game, the map uses the glyph to place
+// one and a random entry with that glyph is picked when it is spawned
+
+use crate::components::StatusKind;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Effect {
+    Heal(isize),
+    // hurts everything the blast can reach from the target cell
+    AreaDamage {
+        radius: isize,
+        damage: isize,
+    },
+    // moves the user to a random open cell
+    Teleport,
+    // puts a timed status effect on whoever is in the target cell
+    Status {
+        kind: StatusKind,
+        turns: usize,
+        potency: isize,
+    },
+    RevealMap,
+    // leaves a light hanging over the target cell
+    Light {
+        radius: isize,
+    },
+    // flies at the target and hurts the first thing in the way
+    Bolt {
+        damage: isize,
+    },
+    // calls up a creature next to the target cell that fights on the side of
+    // the user, the char is what the map would place it with
+    Summon(char),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Targeting {
+    // works on whoever uses it
+    User,
+    // a cell the user can see that is no more then range away
+    Cell { range: isize },
+}
+
+pub struct ConsumableDef {
+    pub name: &'static str,
+    pub glyph: char,
+    pub effect: Effect,
+    pub targeting: Targeting,
+    // how many uses a new one has, wands have charges and the rest are used
+    // up in one go
+    pub uses: usize,
+    // how many can be carried in one slot, things with charges each take a
+    // slot of their own
+    pub max_stack: usize,
+}
+
+pub const CONSUMABLES: [ConsumableDef; 15] = [
+    ConsumableDef {
+        name: "healing potion",
+        glyph: '!',
+        effect: Effect::Heal(5),
+        targeting: Targeting::User,
+        uses: 1,
+        max_stack: 5,
+    },
+    ConsumableDef {
+        name: "scroll of fireball",
+        glyph: '?',
+        effect: Effect::AreaDamage {
+            radius: 2,
+            damage: 4,
+        },
+        targeting: Targeting::Cell { range: 6 },
+        uses: 1,
+        max_stack: 5,
+    },
+    ConsumableDef {
+        name: "scroll of teleport",
+        glyph: '?',
+        effect: Effect::Teleport,
+        targeting: Targeting::User,
+        uses: 1,
+        max_stack: 5,
+    },
+    ConsumableDef {
+        name: "scroll of magic mapping",
+        glyph: '?',
+        effect: Effect::RevealMap,
+        targeting: Targeting::User,
+        uses: 1,
+        max_stack: 5,
+    },
+    ConsumableDef {
+        name: "wand of confusion",
+        glyph: '/',
+        effect: Effect::Status {
+            kind: StatusKind::Confusion,
+            turns: 5,
+            potency: 0,
+        },
+        targeting: Targeting::Cell { range: 6 },
+        uses: 3,
+        max_stack: 1,
+    },
+    ConsumableDef {
+        name: "wand of light",
+        glyph: '/',
+        effect: Effect::Light { radius: 4 },
+        targeting: Targeting::Cell { range: 8 },
+        uses: 3,
+        max_stack: 1,
+    },
+    ConsumableDef {
+        name: "wand of magic missile",
+        glyph: '/',
+        effect: Effect::Bolt { damage: 4 },
+        targeting: Targeting::Cell { range: 8 },
+        uses: 3,
+        max_stack: 1,
+    },
+    ConsumableDef {
+        name: "potion of regeneration",
+        glyph: '!',
+        effect: Effect::Status {
+            kind: StatusKind::Regeneration,
+            turns: 10,
+            potency: 1,
+        },
+        targeting: Targeting::User,
+        uses: 1,
+        max_stack: 5,
+    },
+    ConsumableDef {
+        name: "potion of haste",
+        glyph: '!',
+        effect: Effect::Status {
+            kind: StatusKind::Haste,
+            turns: 15,
+            potency: 0,
+        },
+        targeting: Targeting::User,
+        uses: 1,
+        max_stack: 5,
+    },
+    ConsumableDef {
+        name: "potion of invisibility",
+        glyph: '!',
+        effect: Effect::Status {
+            kind: StatusKind::Invisibility,
+            turns: 15,
+            potency: 0,
+        },
+        targeting: Targeting::User,
+        uses: 1,
+        max_stack: 5,
+    },
+    ConsumableDef {
+        name: "potion of poison",
+        glyph: '!',
+        effect: Effect::Status {
+            kind: StatusKind::Poison,
+            turns: 5,
+            potency: 1,
+        },
+        targeting: Targeting::User,
+        uses: 1,
+        max_stack: 5,
+    },
+    ConsumableDef {
+        name: "potion of blindness",
+        glyph: '!',
+        effect: Effect::Status {
+            kind: StatusKind::Blindness,
+            turns: 8,
+            potency: 0,
+        },
+        targeting: Targeting::User,
+        uses: 1,
+        max_stack: 5,
+    },
+    ConsumableDef {
+        name: "wand of slowness",
+        glyph: '/',
+        effect: Effect::Status {
+            kind: StatusKind::Slow,
+            turns: 10,
+            potency: 0,
+        },
+        targeting: Targeting::Cell { range: 6 },
+        uses: 3,
+        max_stack: 1,
+    },
+    ConsumableDef {
+        name: "wand of paralysis",
+        glyph: '/',
+        effect: Effect::Status {
+            kind: StatusKind::Paralysis,
+            turns: 4,
+            potency: 0,
+        },
+        targeting: Targeting::Cell { range: 6 },
+        uses: 3,
+        max_stack: 1,
+    },
+    ConsumableDef {
+        name: "scroll of summoning",
+        glyph: '?',
+        effect: Effect::Summon('w'),
+        targeting: Targeting::User,
+        uses: 1,
+        max_stack: 5,
+    },
+];
+
+// the indexes of every consumable that is placed with a glyph
+pub fn consumables_for(glyph: char) -> Vec<usize> {
+    CONSUMABLES
+        .iter()
+        .enumerate()
+        .filter(|(_, def)| def.glyph == glyph)
+        .map(|(i, _)| i)
+        .collect()
+}
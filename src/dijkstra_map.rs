@@ -0,0 +1,398 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use crate::{
+    components::Movement,
+    game_map::GameMap,
+    movement::{can_enter, step_cost},
+};
+
+const ADJACENT_SQUARES: [(isize, isize); 8] = [
+    (0, -1),
+    (0, 1),
+    (-1, 0),
+    (1, 0),
+    (-1, -1),
+    (-1, 1),
+    (1, -1),
+    (1, 1),
+];
+
+// how much further then the goal a fleeing entity wants to be, anything over
+// one makes it prefer running past the goal to a far away spot over hiding in
+// a corner next to it
+const FLEE_FACTOR: f64 = -1.2;
+
+// a cell waiting to be scanned, flipped so the heap pops the lowest value
+#[derive(Debug, PartialEq)]
+struct ScanNode {
+    value: f64,
+    index: usize,
+}
+
+impl Eq for ScanNode {}
+
+impl Ord for ScanNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .value
+            .partial_cmp(&self.value)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+impl PartialOrd for ScanNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// the cost from every cell to the nearest goal for one kind of movement,
+// walking down hill always leads to a goal
+#[derive(Clone)]
+pub struct DijkstraMap {
+    pub values: Vec<f64>,
+    pub column_count: usize,
+    pub row_count: usize,
+    pub movement: Movement,
+}
+
+impl DijkstraMap {
+    // each goal has a starting value, a lower value makes a goal more
+    // attractive then the others
+    pub fn new(
+        game_map: &GameMap,
+        movement: Movement,
+        goals: &[((isize, isize), f64)],
+    ) -> Self {
+        Self::limited(game_map, movement, goals, f64::INFINITY)
+    }
+
+    // the same but it stops spreading once the cost gets past the limit,
+    // every cell further away then that is left as unreachable
+    pub fn limited(
+        game_map: &GameMap,
+        movement: Movement,
+        goals: &[((isize, isize), f64)],
+        limit: f64,
+    ) -> Self {
+        let column_count = game_map.map_info.column_count;
+        let row_count = game_map.map_info.row_count;
+
+        let mut dijkstra_map = Self {
+            values: vec![f64::INFINITY; game_map.render_map.len()],
+            column_count,
+            row_count,
+            movement,
+        };
+
+        for (pos, value) in goals {
+            if let Some(index) = dijkstra_map.to_index(*pos) {
+                if *value < dijkstra_map.values[index] {
+                    dijkstra_map.values[index] = *value;
+                }
+            }
+        }
+
+        dijkstra_map.scan(game_map, limit);
+
+        dijkstra_map
+    }
+
+    fn to_index(&self, pos: (isize, isize)) -> Option<usize> {
+        if pos.0 < 0
+            || pos.1 < 0
+            || pos.0 >= self.column_count as isize
+            || pos.1 >= self.row_count as isize
+        {
+            return None;
+        }
+
+        Some(pos.0 as usize + (self.column_count * pos.1 as usize))
+    }
+
+    // spread the current values out until every cell is at most the cost of
+    // one step more then its lowest neighbor
+    fn scan(&mut self, game_map: &GameMap, limit: f64) {
+        let mut open_list = BinaryHeap::new();
+
+        for (index, value) in self.values.iter().enumerate() {
+            if value.is_finite() {
+                open_list.push(ScanNode {
+                    value: *value,
+                    index,
+                });
+            }
+        }
+
+        while let Some(ScanNode { value, index }) = open_list.pop() {
+            // this cell was lowered after it was pushed
+            if value > self.values[index] {
+                continue;
+            }
+
+            let x = (index % self.column_count) as isize;
+            let y = (index / self.column_count) as isize;
+
+            for adjust in ADJACENT_SQUARES.iter() {
+                let n_pos = (x + adjust.0, y + adjust.1);
+
+                let n_index = if let Some(n_index) = self.to_index(n_pos) {
+                    n_index
+                } else {
+                    continue;
+                };
+
+                // step_cost only looks at where a step ends, so a cell the
+                // entity cant stand in would get a value without this
+                if !can_enter(game_map, &self.movement, n_index) {
+                    continue;
+                }
+
+                // entitys walk from the neighbor to this cell so that is the
+                // step that has to be possible
+                let cost = if let Some(cost) =
+                    step_cost(game_map, &self.movement, n_pos, (x, y))
+                {
+                    cost
+                } else {
+                    continue;
+                };
+
+                let new_value = value + cost as f64;
+
+                if new_value < self.values[n_index] && new_value <= limit {
+                    self.values[n_index] = new_value;
+
+                    open_list.push(ScanNode {
+                        value: new_value,
+                        index: n_index,
+                    });
+                }
+            }
+        }
+    }
+
+    pub fn value(&self, pos: (isize, isize)) -> Option<f64> {
+        let index = self.to_index(pos)?;
+
+        let value = self.values[index];
+
+        if value.is_finite() {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    // multiply every value, a bigger weight makes this map matter more when
+    // it is combined with others
+    pub fn weight(mut self, weight: f64) -> Self {
+        for value in self.values.iter_mut() {
+            if value.is_finite() {
+                *value *= weight;
+            }
+        }
+
+        self
+    }
+
+    // add another map on top of this one, cells either map cant reach stay
+    // unreachable
+    pub fn combine(mut self, other: &DijkstraMap, weight: f64) -> Self {
+        for (value, other_value) in self.values.iter_mut().zip(&other.values) {
+            if value.is_finite() && other_value.is_finite() {
+                *value += other_value * weight;
+            } else {
+                *value = f64::INFINITY;
+            }
+        }
+
+        self
+    }
+
+    // turn a map that leads to the goals in to one that leads away from them,
+    // the rescan lets entities find a way around the goal instead of getting
+    // stuck in a dead end
+    pub fn flee(&self, game_map: &GameMap) -> Self {
+        let mut flee_map = Self {
+            values: self.values.clone(),
+            column_count: self.column_count,
+            row_count: self.row_count,
+            movement: self.movement,
+        }
+        .weight(FLEE_FACTOR);
+
+        flee_map.scan(game_map, f64::INFINITY);
+
+        flee_map
+    }
+
+    // the neighbors that are lower then pos, best first
+    pub fn downhill(&self, pos: (isize, isize)) -> Vec<(isize, isize)> {
+        let current = if let Some(current) = self.value(pos) {
+            current
+        } else {
+            return vec![];
+        };
+
+        let mut steps = vec![];
+
+        for adjust in ADJACENT_SQUARES.iter() {
+            let n_pos = (pos.0 + adjust.0, pos.1 + adjust.1);
+
+            if let Some(value) = self.value(n_pos) {
+                if value < current {
+                    steps.push((value, n_pos));
+                }
+            }
+        }
+
+        steps.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+
+        steps.into_iter().map(|(_, n_pos)| n_pos).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_map::test_map;
+
+    // a room with a dead end corridor off to the right
+    const MAP: [&str; 5] =
+        ["######", "#...##", "#.......", "#...####", "######"];
+
+    fn map() -> GameMap {
+        let rows: Vec<String> =
+            MAP.iter().map(|row| format!("{:#<8}", row)).collect();
+
+        let rows: Vec<&str> = rows.iter().map(|row| row.as_str()).collect();
+
+        test_map(&rows)
+    }
+
+    #[test]
+    fn values_are_the_steps_to_the_nearest_goal() {
+        let game_map = map();
+
+        let dijkstra_map = DijkstraMap::new(
+            &game_map,
+            Movement::default(),
+            &[((1, 1), 0.0), ((7, 2), 2.0)],
+        );
+
+        assert_eq!(dijkstra_map.value((1, 1)), Some(0.0));
+        assert_eq!(dijkstra_map.value((3, 3)), Some(2.0));
+        // the second goal starts higher so it only wins near it
+        assert_eq!(dijkstra_map.value((7, 2)), Some(2.0));
+        assert_eq!(dijkstra_map.value((5, 2)), Some(4.0));
+        // walls and cells off the map can not be reached
+        assert_eq!(dijkstra_map.value((0, 0)), None);
+        assert_eq!(dijkstra_map.value((-1, 2)), None);
+    }
+
+    #[test]
+    fn limited_stops_at_the_limit() {
+        let game_map = map();
+
+        let full =
+            DijkstraMap::new(&game_map, Movement::default(), &[((1, 2), 0.0)]);
+
+        let limited = DijkstraMap::limited(
+            &game_map,
+            Movement::default(),
+            &[((1, 2), 0.0)],
+            3.0,
+        );
+
+        for (index, value) in full.values.iter().enumerate() {
+            let expected = if *value <= 3.0 { *value } else { f64::INFINITY };
+
+            assert_eq!(limited.values[index], expected, "cell {}", index);
+        }
+
+        assert_eq!(limited.value((4, 2)), Some(3.0));
+        assert_eq!(limited.value((5, 2)), None);
+    }
+
+    #[test]
+    fn downhill_is_lower_neighbors_best_first() {
+        let game_map = map();
+
+        let dijkstra_map =
+            DijkstraMap::new(&game_map, Movement::default(), &[((1, 1), 0.0)]);
+
+        let steps = dijkstra_map.downhill((3, 3));
+
+        assert_eq!(steps.first(), Some(&(2, 2)));
+
+        let values: Vec<f64> = steps
+            .iter()
+            .map(|step| dijkstra_map.value(*step).unwrap())
+            .collect();
+
+        assert!(values.iter().all(|value| *value < 2.0));
+        assert!(values.windows(2).all(|pair| pair[0] <= pair[1]));
+
+        // there is nowhere lower to go from the goal or from a wall
+        assert!(dijkstra_map.downhill((1, 1)).is_empty());
+        assert!(dijkstra_map.downhill((0, 0)).is_empty());
+    }
+
+    #[test]
+    fn fleeing_runs_past_the_threat_instead_of_into_a_corner() {
+        // a small room with a long corridor leading off it
+        let game_map = test_map(&[
+            "###################",
+            "#.....#############",
+            "#.................#",
+            "#.....#############",
+            "###################",
+        ]);
+
+        // the threat stands in the middle of the room
+        let chase_map =
+            DijkstraMap::new(&game_map, Movement::default(), &[((3, 2), 0.0)]);
+
+        let flee_map = chase_map.flee(&game_map);
+
+        assert_eq!(chase_map.value((1, 1)), Some(2.0));
+        assert_eq!(flee_map.value((1, 1)), Some(2.0 * FLEE_FACTOR));
+
+        // the corner behind it is further away then where it stands, but the
+        // end of the corridor is much further so it goes round the threat
+        let steps = flee_map.downhill((2, 2));
+
+        assert!(steps.contains(&(1, 2)));
+        assert_eq!(steps[0].0, 3);
+        assert!(flee_map.value((3, 1)) < flee_map.value((1, 2)));
+
+        // once past it keeps going down the corridor
+        assert_eq!(flee_map.downhill((6, 2)).first(), Some(&(7, 2)));
+        assert!(flee_map.downhill((17, 2)).is_empty());
+    }
+
+    #[test]
+    fn combine_adds_the_maps_and_keeps_unreachable_cells() {
+        let game_map = map();
+
+        let to_room =
+            DijkstraMap::new(&game_map, Movement::default(), &[((1, 1), 0.0)]);
+
+        let to_corridor = DijkstraMap::limited(
+            &game_map,
+            Movement::default(),
+            &[((7, 2), 0.0)],
+            4.0,
+        );
+
+        let combined = to_room.clone().weight(2.0).combine(&to_corridor, 0.5);
+
+        assert_eq!(combined.value((6, 2)), Some(2.0 * 5.0 + 0.5 * 1.0));
+
+        // out of the corridor map's reach so it is out of the combined one's
+        assert_eq!(to_room.value((1, 1)), Some(0.0));
+        assert_eq!(combined.value((1, 1)), None);
+    }
+}
@@ -0,0 +1,121 @@
+// how the factions get on with each other, anything without a faction is left
+// alone by everyone
+
+use crate::components::{ComponentStore, FactionKind};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Relation {
+    Hostile,
+    Neutral,
+    Allied,
+}
+
+// rows are the faction doing the looking, in the same order as FactionKind
+const RELATIONS: [[Relation; 5]; 5] = [
+    // player
+    [
+        Relation::Allied,
+        Relation::Hostile,
+        Relation::Hostile,
+        Relation::Neutral,
+        Relation::Hostile,
+    ],
+    // undead
+    [
+        Relation::Hostile,
+        Relation::Allied,
+        Relation::Hostile,
+        Relation::Neutral,
+        Relation::Hostile,
+    ],
+    // beasts
+    [
+        Relation::Hostile,
+        Relation::Hostile,
+        Relation::Allied,
+        Relation::Neutral,
+        Relation::Hostile,
+    ],
+    // town
+    [
+        Relation::Neutral,
+        Relation::Neutral,
+        Relation::Neutral,
+        Relation::Allied,
+        Relation::Neutral,
+    ],
+    // rival
+    [
+        Relation::Hostile,
+        Relation::Hostile,
+        Relation::Hostile,
+        Relation::Neutral,
+        Relation::Allied,
+    ],
+];
+
+// where a faction is in the table, the rivals all share the last row
+fn row(kind: FactionKind) -> usize {
+    match kind {
+        FactionKind::Player => 0,
+        FactionKind::Undead => 1,
+        FactionKind::Beasts => 2,
+        FactionKind::Town => 3,
+        FactionKind::Rival(_) => 4,
+    }
+}
+
+pub fn relation(from: FactionKind, to: FactionKind) -> Relation {
+    match (from, to) {
+        // two players are never on the same side
+        (FactionKind::Rival(a), FactionKind::Rival(b)) if a != b => {
+            Relation::Hostile
+        }
+        _ => RELATIONS[row(from)][row(to)],
+    }
+}
+
+pub fn relation_between(
+    components: &ComponentStore,
+    from: usize,
+    to: usize,
+) -> Relation {
+    if from == to {
+        return Relation::Allied;
+    }
+
+    match (components.faction.get(&from), components.faction.get(&to)) {
+        (Some(from), Some(to)) => relation(from.kind, to.kind),
+        _ => Relation::Neutral,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_player_is_on_their_own_side() {
+        let sides = [
+            FactionKind::Player,
+            FactionKind::Rival(1),
+            FactionKind::Rival(2),
+            FactionKind::Rival(3),
+        ];
+
+        for from in sides.iter() {
+            for to in sides.iter() {
+                let expected = if from == to {
+                    Relation::Allied
+                } else {
+                    Relation::Hostile
+                };
+
+                assert_eq!(relation(*from, *to), expected);
+            }
+
+            assert_eq!(relation(*from, FactionKind::Town), Relation::Neutral);
+            assert_eq!(relation(*from, FactionKind::Undead), Relation::Hostile);
+        }
+    }
+}
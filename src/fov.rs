@@ -19,11 +19,26 @@ struct ShadowData {
     row_count: isize,
 }
 
-pub fn fov(game_map: &mut GameMap, view_point: (usize, usize)) {
+pub fn fov(game_map: &mut GameMap, view_point: (usize, usize), radius: isize) {
+    for cel_ind in visible_cells(game_map, view_point, radius) {
+        let cell = &mut game_map.render_map[cel_ind];
+
+        cell.lit = true;
+        cell.visited = true;
+    }
+}
+
+// the indexes of every cell that can be seen from view_point, this dose not
+// touch the map so other systems like lighting can use it
+pub fn visible_cells(
+    game_map: &GameMap,
+    view_point: (usize, usize),
+    radius: isize,
+) -> Vec<usize> {
     let ind =
         view_point.0 + (game_map.map_info.column_count as usize * view_point.1);
 
-    game_map.render_map[ind].lit = true;
+    let mut cells = vec![ind];
 
     for region in 0..8 {
         let mut shadow_data = ShadowData {
@@ -31,20 +46,34 @@ pub fn fov(game_map: &mut GameMap, view_point: (usize, usize)) {
             row_count: game_map.map_info.row_count as isize,
             view_x: view_point.0 as isize,
             view_y: view_point.1 as isize,
-            radius: 5,
+            radius,
             xx: MULT[0][region],
             xy: MULT[1][region],
             yx: MULT[2][region],
             yy: MULT[3][region],
         };
 
-        recursive_shadowcasting(game_map, &mut shadow_data, 1, 1.0f64, 0.0f64);
+        recursive_shadowcasting(
+            game_map,
+            &mut shadow_data,
+            &mut cells,
+            1,
+            1.0f64,
+            0.0f64,
+        );
     }
+
+    // the regions overlap on the edges so some cells get pushed twice
+    cells.sort_unstable();
+    cells.dedup();
+
+    cells
 }
 
 fn recursive_shadowcasting(
-    game_map: &mut GameMap,
+    game_map: &GameMap,
     shadow_data: &mut ShadowData,
+    cells: &mut Vec<usize>,
     row: isize,
     start: f64,
     end: f64,
@@ -103,10 +132,7 @@ fn recursive_shadowcasting(
             let cel_ind = (grid_x + (map_width * grid_y)) as usize;
 
             if distance_squer < view_radius_square {
-                let cell = &mut game_map.render_map[cel_ind];
-
-                cell.lit = true;
-                cell.visited = true;
+                cells.push(cel_ind);
             }
 
             let cur_blocked =
@@ -124,6 +150,7 @@ fn recursive_shadowcasting(
                     recursive_shadowcasting(
                         game_map,
                         shadow_data,
+                        cells,
                         cur_col + 1,
                         left_view_slope,
                         left_block_slope,
@@ -1,20 +1,37 @@
-// components::{Ai, AiType, ComponentStore, EntitySize, Render},
-
 use crate::{
     components::{ComponentStore, EntitySize},
     entitys::Entitys,
+    map_gen::spawner::spawn,
 };
 
 pub struct RenderCell {
     pub lit: bool,
     pub visible: bool,
     pub visited: bool,
+    pub light_level: f64,
+    pub light_color: (u8, u8, u8),
     pub ent_size: EntitySize,
     pub ent_char: char,
     pub terrain_size: EntitySize,
     pub terrain_char: char,
 }
 
+// what a cell is made of, this is what movement costs are based on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Terrain {
+    Floor,
+    Water,
+    Wall,
+}
+
+// something about a cell changed, anything that keeps its own state about the
+// map uses these to know what to throw away
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MapChange {
+    Terrain(usize),
+    Occupancy(usize),
+}
+
 #[derive(Clone)]
 pub struct MapInfo {
     pub column_count: usize,
@@ -26,6 +43,7 @@ pub struct GameMap {
     pub terrain_map: Vec<usize>,
     pub render_map: Vec<RenderCell>,
     pub map_info: MapInfo,
+    pub changes: Vec<MapChange>,
 }
 
 impl GameMap {
@@ -34,29 +52,42 @@ impl GameMap {
             terrain_map: Vec::new(),
             render_map: Vec::new(),
             map_info,
+            changes: Vec::new(),
         }
     }
 
     pub fn init_map(
         &mut self,
-        _components: &mut ComponentStore,
+        components: &mut ComponentStore,
         entitys: &mut Entitys,
         char_map: Vec<char>,
     ) {
-        for map_char in char_map.iter() {
+        for (i, map_char) in char_map.iter().enumerate() {
             let terrain_id = entitys.new_id();
 
             let mut render_cell = RenderCell {
                 visited: false,
                 lit: false,
                 visible: false,
+                light_level: 0.0,
+                light_color: (0, 0, 0),
                 ent_size: EntitySize::Nothing,
                 terrain_size: EntitySize::Nothing,
                 ent_char: ' ',
                 terrain_char: ' ',
             };
 
-            if *map_char != ' ' {
+            if spawn(components, entitys, *map_char, i).is_some() {
+                // entitys stand on the floor so the terrain stays empty
+            } else if *map_char == '~' {
+                // water can be seen over and waded through
+                render_cell.terrain_char = *map_char;
+                render_cell.terrain_size = EntitySize::Small;
+                render_cell.ent_char = *map_char;
+                render_cell.ent_size = EntitySize::Small;
+
+                render_cell.visible = true;
+            } else if *map_char != ' ' {
                 render_cell.terrain_char = *map_char;
                 render_cell.terrain_size = EntitySize::Medium;
                 render_cell.ent_char = *map_char;
@@ -70,4 +101,60 @@ impl GameMap {
             self.render_map.push(render_cell);
         }
     }
+
+    pub fn terrain(&self, index: usize) -> Terrain {
+        match self.render_map[index].terrain_char {
+            ' ' => Terrain::Floor,
+            '~' => Terrain::Water,
+            _ => Terrain::Wall,
+        }
+    }
+
+    // change what a cell is made of, for things like doors opening or digging
+    pub fn set_terrain(&mut self, index: usize, terrain_char: char) {
+        let cell = &mut self.render_map[index];
+
+        cell.terrain_char = terrain_char;
+        cell.terrain_size = match terrain_char {
+            ' ' => EntitySize::Nothing,
+            '~' => EntitySize::Small,
+            _ => EntitySize::Medium,
+        };
+
+        cell.ent_char = cell.terrain_char;
+        cell.ent_size = cell.terrain_size;
+
+        self.notify(MapChange::Terrain(index));
+    }
+
+    pub fn notify(&mut self, change: MapChange) {
+        self.changes.push(change);
+    }
+}
+
+// a small map drawn with rows of text for the tests, a dot is floor
+#[cfg(test)]
+pub fn test_map(rows: &[&str]) -> GameMap {
+    let column_count = rows[0].len();
+    let row_count = rows.len();
+
+    let mut game_map = GameMap::new(MapInfo {
+        column_count,
+        row_count,
+        total_count: column_count * row_count,
+    });
+
+    let char_map: Vec<char> = rows
+        .iter()
+        .flat_map(|row| row.chars())
+        .map(|map_char| if map_char == '.' { ' ' } else { map_char })
+        .collect();
+
+    game_map.init_map(
+        &mut ComponentStore::default(),
+        &mut Entitys::new(),
+        char_map,
+    );
+
+    game_map
 }
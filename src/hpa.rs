@@ -0,0 +1,601 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+use crate::{
+    astar::{astar, astar_within, Bounds},
+    components::Movement,
+    game_map::GameMap,
+    movement::{can_enter, step_cost},
+};
+
+// how many cells wide and tall each cluster is
+const CLUSTER_SIZE: isize = 10;
+
+// a run of open cells along a border at least this long gets an entrance at
+// both ends instead of just one in the middle
+const LONG_ENTRANCE: usize = 6;
+
+type Pos = (isize, isize);
+
+// a node in the abstract search, flipped so the heap pops the lowest f first
+#[derive(Debug, PartialEq, Eq)]
+struct OpenNode {
+    f: usize,
+    g: usize,
+    pos: Pos,
+}
+
+impl Ord for OpenNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .f
+            .cmp(&self.f)
+            .then_with(|| self.g.cmp(&other.g))
+            .then_with(|| other.pos.cmp(&self.pos))
+    }
+}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn distance(from: Pos, to: Pos) -> usize {
+    (from.0 - to.0).abs().max((from.1 - to.1).abs()) as usize
+}
+
+fn union(a: &Bounds, b: &Bounds) -> Bounds {
+    Bounds {
+        x1: a.x1.min(b.x1),
+        y1: a.y1.min(b.y1),
+        x2: a.x2.max(b.x2),
+        y2: a.y2.max(b.y2),
+    }
+}
+
+// the map cut up in to clusters joined by entrances, a path is planned over
+// the entrances first and then filled in one cluster at a time
+pub struct PathGraph {
+    pub movement: Movement,
+    column_count: isize,
+    row_count: isize,
+    clusters_x: isize,
+    clusters_y: isize,
+    // the entrances between two clusters keyed by the lower cluster id first,
+    // each entrance is the cell in the lower cluster and the cell in the other
+    borders: HashMap<(usize, usize), Vec<(Pos, Pos)>>,
+    // what it costs to get between the entrances inside of each cluster
+    intra_edges: Vec<HashMap<Pos, Vec<(Pos, usize)>>>,
+}
+
+impl PathGraph {
+    pub fn new(game_map: &GameMap, movement: Movement) -> Self {
+        let column_count = game_map.map_info.column_count as isize;
+        let row_count = game_map.map_info.row_count as isize;
+
+        let clusters_x = (column_count + CLUSTER_SIZE - 1) / CLUSTER_SIZE;
+        let clusters_y = (row_count + CLUSTER_SIZE - 1) / CLUSTER_SIZE;
+
+        let cluster_count = (clusters_x * clusters_y) as usize;
+
+        let mut path_graph = Self {
+            movement,
+            column_count,
+            row_count,
+            clusters_x,
+            clusters_y,
+            borders: HashMap::new(),
+            intra_edges: vec![HashMap::new(); cluster_count],
+        };
+
+        for cluster in 0..cluster_count {
+            for other in path_graph.higher_neighbors(cluster) {
+                path_graph.build_border(game_map, cluster, other);
+            }
+        }
+
+        for cluster in 0..cluster_count {
+            path_graph.build_cluster(game_map, cluster);
+        }
+
+        path_graph
+    }
+
+    fn cluster_of(&self, pos: Pos) -> usize {
+        ((pos.0 / CLUSTER_SIZE) + (self.clusters_x * (pos.1 / CLUSTER_SIZE)))
+            as usize
+    }
+
+    fn cluster_bounds(&self, cluster: usize) -> Bounds {
+        let cx = cluster as isize % self.clusters_x;
+        let cy = cluster as isize / self.clusters_x;
+
+        Bounds {
+            x1: cx * CLUSTER_SIZE,
+            y1: cy * CLUSTER_SIZE,
+            x2: ((cx + 1) * CLUSTER_SIZE).min(self.column_count) - 1,
+            y2: ((cy + 1) * CLUSTER_SIZE).min(self.row_count) - 1,
+        }
+    }
+
+    // the clusters to the right and below
+    fn higher_neighbors(&self, cluster: usize) -> Vec<usize> {
+        let cx = cluster as isize % self.clusters_x;
+        let cy = cluster as isize / self.clusters_x;
+
+        let mut neighbors = vec![];
+
+        if cx + 1 < self.clusters_x {
+            neighbors.push(cluster + 1);
+        }
+
+        if cy + 1 < self.clusters_y {
+            neighbors.push(cluster + self.clusters_x as usize);
+        }
+
+        neighbors
+    }
+
+    fn neighbors(&self, cluster: usize) -> Vec<usize> {
+        let cx = cluster as isize % self.clusters_x;
+        let cy = cluster as isize / self.clusters_x;
+
+        let mut neighbors = self.higher_neighbors(cluster);
+
+        if cx > 0 {
+            neighbors.push(cluster - 1);
+        }
+
+        if cy > 0 {
+            neighbors.push(cluster - self.clusters_x as usize);
+        }
+
+        neighbors
+    }
+
+    fn open_pair(&self, game_map: &GameMap, a: Pos, b: Pos) -> bool {
+        let index = |pos: Pos| (pos.0 + (self.column_count * pos.1)) as usize;
+
+        can_enter(game_map, &self.movement, index(a))
+            && can_enter(game_map, &self.movement, index(b))
+            && step_cost(game_map, &self.movement, a, b).is_some()
+            && step_cost(game_map, &self.movement, b, a).is_some()
+    }
+
+    // find the entrances along the border between two clusters, low has to
+    // be above or to the left of high
+    fn build_border(&mut self, game_map: &GameMap, low: usize, high: usize) {
+        let low_bounds = self.cluster_bounds(low);
+
+        let is_right = high == low + 1 && high as isize % self.clusters_x != 0;
+
+        // every pair of cells facing each other across the border
+        let pairs: Vec<(Pos, Pos)> = if is_right {
+            (low_bounds.y1..=low_bounds.y2)
+                .map(|y| ((low_bounds.x2, y), (low_bounds.x2 + 1, y)))
+                .collect()
+        } else {
+            (low_bounds.x1..=low_bounds.x2)
+                .map(|x| ((x, low_bounds.y2), (x, low_bounds.y2 + 1)))
+                .collect()
+        };
+
+        let mut entrances = vec![];
+        let mut run: Vec<(Pos, Pos)> = vec![];
+
+        // a closed pair on the end makes sure the last run gets added
+        for pair in pairs.iter().map(Some).chain(std::iter::once(None)) {
+            if let Some(pair) = pair {
+                if self.open_pair(game_map, pair.0, pair.1) {
+                    run.push(*pair);
+
+                    continue;
+                }
+            }
+
+            if run.len() >= LONG_ENTRANCE {
+                entrances.push(run[0]);
+                entrances.push(run[run.len() - 1]);
+            } else if !run.is_empty() {
+                entrances.push(run[run.len() / 2]);
+            }
+
+            run.clear();
+        }
+
+        self.borders.insert((low, high), entrances);
+    }
+
+    // the cells inside of a cluster that lead to another cluster
+    fn entrance_cells(&self, cluster: usize) -> Vec<Pos> {
+        let mut cells = vec![];
+
+        for other in self.neighbors(cluster) {
+            let key = (cluster.min(other), cluster.max(other));
+
+            if let Some(entrances) = self.borders.get(&key) {
+                for (low_cell, high_cell) in entrances {
+                    if cluster < other {
+                        cells.push(*low_cell);
+                    } else {
+                        cells.push(*high_cell);
+                    }
+                }
+            }
+        }
+
+        cells.sort_unstable();
+        cells.dedup();
+
+        cells
+    }
+
+    // how much it costs to get from every entrance in a cluster to the others
+    fn build_cluster(&mut self, game_map: &GameMap, cluster: usize) {
+        let bounds = self.cluster_bounds(cluster);
+
+        let cells = self.entrance_cells(cluster);
+
+        let nobody = HashSet::new();
+
+        let mut edges = HashMap::new();
+
+        for from in cells.iter() {
+            let mut from_edges = vec![];
+
+            for to in cells.iter().filter(|to| *to != from) {
+                if let Some((_, cost)) = astar_within(
+                    game_map,
+                    *from,
+                    *to,
+                    &self.movement,
+                    &nobody,
+                    &bounds,
+                ) {
+                    from_edges.push((*to, cost));
+                }
+            }
+
+            edges.insert(*from, from_edges);
+        }
+
+        self.intra_edges[cluster] = edges;
+    }
+
+    // fix up the parts of the graph around cells whose terrain changed
+    pub fn update(&mut self, game_map: &GameMap, changed: &[usize]) {
+        let dirty: HashSet<usize> = changed
+            .iter()
+            .map(|index| {
+                let index = *index as isize;
+
+                self.cluster_of((
+                    index % self.column_count,
+                    index / self.column_count,
+                ))
+            })
+            .collect();
+
+        // the entrances of a dirty cluster also belong to its neighbors so
+        // they need their costs redone too
+        let mut rebuild = HashSet::new();
+
+        for cluster in dirty {
+            rebuild.insert(cluster);
+
+            for other in self.neighbors(cluster) {
+                self.build_border(
+                    game_map,
+                    cluster.min(other),
+                    cluster.max(other),
+                );
+
+                rebuild.insert(other);
+            }
+        }
+
+        for cluster in rebuild {
+            self.build_cluster(game_map, cluster);
+        }
+    }
+
+    // the entrances that can be reached from pos in one step of the graph
+    fn graph_edges(&self, game_map: &GameMap, pos: Pos) -> Vec<(Pos, usize)> {
+        let cluster = self.cluster_of(pos);
+
+        let mut edges = self.intra_edges[cluster]
+            .get(&pos)
+            .cloned()
+            .unwrap_or_default();
+
+        for other in self.neighbors(cluster) {
+            let key = (cluster.min(other), cluster.max(other));
+
+            if let Some(entrances) = self.borders.get(&key) {
+                for (low_cell, high_cell) in entrances {
+                    let (inside, outside) = if cluster < other {
+                        (*low_cell, *high_cell)
+                    } else {
+                        (*high_cell, *low_cell)
+                    };
+
+                    if inside != pos {
+                        continue;
+                    }
+
+                    if let Some(cost) =
+                        step_cost(game_map, &self.movement, inside, outside)
+                    {
+                        edges.push((outside, cost));
+                    }
+                }
+            }
+        }
+
+        edges
+    }
+
+    // the cost from pos to every entrance of its cluster, or from every
+    // entrance to pos when to_pos is set
+    fn connect(
+        &self,
+        game_map: &GameMap,
+        pos: Pos,
+        to_pos: bool,
+    ) -> HashMap<Pos, usize> {
+        let cluster = self.cluster_of(pos);
+        let bounds = self.cluster_bounds(cluster);
+
+        let nobody = HashSet::new();
+
+        let mut costs = HashMap::new();
+
+        for cell in self.entrance_cells(cluster) {
+            let (from, to) = if to_pos { (cell, pos) } else { (pos, cell) };
+
+            if let Some((_, cost)) = astar_within(
+                game_map,
+                from,
+                to,
+                &self.movement,
+                &nobody,
+                &bounds,
+            ) {
+                costs.insert(cell, cost);
+            }
+        }
+
+        costs
+    }
+
+    // plan over the entrances, this gives the cells the path has to go
+    // through from start to end
+    fn waypoints(
+        &self,
+        game_map: &GameMap,
+        start: Pos,
+        end: Pos,
+    ) -> Option<Vec<Pos>> {
+        let start_edges = self.connect(game_map, start, false);
+        let end_edges = self.connect(game_map, end, true);
+
+        let mut g_score: HashMap<Pos, usize> = HashMap::new();
+        let mut came_from: HashMap<Pos, Pos> = HashMap::new();
+        let mut closed: HashSet<Pos> = HashSet::new();
+
+        let mut open_list = BinaryHeap::new();
+
+        g_score.insert(start, 0);
+
+        open_list.push(OpenNode {
+            f: distance(start, end),
+            g: 0,
+            pos: start,
+        });
+
+        while let Some(OpenNode { g, pos, .. }) = open_list.pop() {
+            if pos == end {
+                let mut waypoints = vec![end];
+                let mut current = end;
+
+                while let Some(parent) = came_from.get(&current) {
+                    waypoints.push(*parent);
+                    current = *parent;
+                }
+
+                waypoints.reverse();
+
+                return Some(waypoints);
+            }
+
+            if !closed.insert(pos) {
+                continue;
+            }
+
+            let mut edges = self.graph_edges(game_map, pos);
+
+            if pos == start {
+                edges.extend(start_edges.iter().map(|(p, c)| (*p, *c)));
+            }
+
+            if let Some(cost) = end_edges.get(&pos) {
+                edges.push((end, *cost));
+            }
+
+            for (next, cost) in edges {
+                if closed.contains(&next) {
+                    continue;
+                }
+
+                let new_g = g + cost;
+
+                if new_g < *g_score.get(&next).unwrap_or(&usize::MAX) {
+                    g_score.insert(next, new_g);
+                    came_from.insert(next, pos);
+
+                    open_list.push(OpenNode {
+                        f: new_g + distance(next, end),
+                        g: new_g,
+                        pos: next,
+                    });
+                }
+            }
+        }
+
+        None
+    }
+
+    // works like astar, short paths and other kinds of movement just use it
+    // directly
+    pub fn find_path(
+        &self,
+        game_map: &GameMap,
+        start: Pos,
+        end: Pos,
+        movement: &Movement,
+        occupied: &HashSet<usize>,
+    ) -> Option<Vec<Pos>> {
+        if *movement != self.movement
+            || distance(start, end) <= CLUSTER_SIZE as usize
+        {
+            return astar(game_map, start, end, movement, occupied);
+        }
+
+        let waypoints = self.waypoints(game_map, start, end)?;
+
+        let mut path = vec![start];
+
+        // fill in between each waypoint, the search only has to look at the
+        // clusters the two waypoints are in
+        for pair in waypoints.windows(2) {
+            let bounds = union(
+                &self.cluster_bounds(self.cluster_of(pair[0])),
+                &self.cluster_bounds(self.cluster_of(pair[1])),
+            );
+
+            match astar_within(
+                game_map, pair[0], pair[1], movement, occupied, &bounds,
+            ) {
+                Some((segment, _)) => path.extend(segment.into_iter().skip(1)),
+                // someone is standing in the way, find a path the slow way
+                None => return astar(game_map, start, end, movement, occupied),
+            }
+        }
+
+        Some(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // three clusters across and two down, split in three by two walls that
+    // run from top to bottom
+    fn split_map() -> GameMap {
+        let rows: Vec<String> = (0..20)
+            .map(|y| {
+                (0..30)
+                    .map(|x| {
+                        let edge = x == 0 || y == 0 || x == 29 || y == 19;
+
+                        if edge || x == 10 || x == 15 {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let rows: Vec<&str> = rows.iter().map(|row| row.as_str()).collect();
+
+        crate::game_map::test_map(&rows)
+    }
+
+    fn index(game_map: &GameMap, pos: Pos) -> usize {
+        (pos.0 + game_map.map_info.column_count as isize * pos.1) as usize
+    }
+
+    // every step has to be to a cell next to the last one that can be walked
+    fn assert_walkable(game_map: &GameMap, path: &[Pos], start: Pos, end: Pos) {
+        assert_eq!(path.first(), Some(&start));
+        assert_eq!(path.last(), Some(&end));
+
+        for pair in path.windows(2) {
+            assert_eq!(distance(pair[0], pair[1]), 1, "{:?}", pair);
+            assert!(
+                step_cost(game_map, &Movement::default(), pair[0], pair[1])
+                    .is_some(),
+                "{:?}",
+                pair
+            );
+        }
+    }
+
+    fn assert_same_graph(updated: &PathGraph, game_map: &GameMap) {
+        let rebuilt = PathGraph::new(game_map, updated.movement);
+
+        assert_eq!(updated.borders, rebuilt.borders);
+        assert_eq!(updated.intra_edges, rebuilt.intra_edges);
+    }
+
+    #[test]
+    fn update_matches_a_full_rebuild() {
+        let mut game_map = split_map();
+
+        let mut path_graph = PathGraph::new(&game_map, Movement::default());
+
+        let start = (3, 15);
+        let end = (26, 4);
+
+        let nobody = HashSet::new();
+
+        let find = |path_graph: &PathGraph, game_map: &GameMap| {
+            path_graph.find_path(
+                game_map,
+                start,
+                end,
+                &Movement::default(),
+                &nobody,
+            )
+        };
+
+        assert_eq!(find(&path_graph, &game_map), None);
+
+        // one gap on a cluster border and one in the middle of a cluster
+        let first = index(&game_map, (10, 3));
+        let second = index(&game_map, (15, 17));
+
+        game_map.set_terrain(first, ' ');
+        game_map.set_terrain(second, ' ');
+
+        path_graph.update(&game_map, &[first, second]);
+
+        assert_same_graph(&path_graph, &game_map);
+
+        let path = find(&path_graph, &game_map).unwrap();
+
+        assert_walkable(&game_map, &path, start, end);
+        assert!(path.contains(&(10, 3)));
+        assert!(path.contains(&(15, 17)));
+
+        // the long way round still has to be close to the best there is
+        let best = astar(&game_map, start, end, &Movement::default(), &nobody)
+            .unwrap();
+
+        assert!(path.len() <= best.len() + best.len() / 4);
+
+        // closing it again cuts the map back in two
+        game_map.set_terrain(second, '#');
+
+        path_graph.update(&game_map, &[second]);
+
+        assert_same_graph(&path_graph, &game_map);
+        assert_eq!(find(&path_graph, &game_map), None);
+    }
+}
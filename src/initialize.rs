@@ -1,4 +1,4 @@
-use std::error::Error;
+use std::{cmp::Ordering, collections::HashMap, error::Error};
 
 use sdl2::{
     pixels::Color,
@@ -6,12 +6,23 @@ use sdl2::{
 };
 
 use crate::{
-    components::{EntitySize, Health, Name, Render},
+    classes::CLASSES,
+    components::{
+        Abilities, Attack, Class, Defense, EntitySize, Equipment, Experience,
+        Faction, FactionKind, Health, Inventory, LightSource, Movement, Name,
+        Render, Vision,
+    },
+    dijkstra_map::DijkstraMap,
     entitys::Entitys,
+    map_gen::spawner::spawn_carried,
+    movement::{can_enter, occupied_cells},
     scenes::Scene,
     ContextManager, WindowInfo,
 };
 
+// how far apart the players start in a versus game
+const RIVAL_DISTANCE: f64 = 12.0;
+
 pub fn init_screen(
     window_info: &WindowInfo,
 ) -> Result<ContextManager, Box<dyn Error>> {
@@ -58,29 +69,33 @@ pub fn init_font<'ttf, 'r>(
     Ok(font)
 }
 
+// put the player on the map with the things every class has, the rest comes
+// from the class picked in create_character
 pub fn init_player(
     scene: &mut Scene,
     entitys: &mut Entitys,
-    center: (usize, usize),
+    index: usize,
+    kind: FactionKind,
 ) {
     let player_id = entitys.new_id();
 
-    let index = center.0 + (scene.game_map.map_info.column_count * center.1);
+    scene.players.push(player_id);
 
-    scene.player = player_id;
+    // the first player always goes first
+    scene.player = scene.players[0];
 
-    scene.components.name.insert(
+    scene.components.inventory.insert(
         player_id,
-        Name {
-            value: "test player".to_string(),
+        Inventory {
+            items: Vec::new(),
+            capacity: 26,
         },
     );
 
-    scene.components.health.insert(
+    scene.components.equipment.insert(
         player_id,
-        Health {
-            max_value: 10,
-            cur_value: 10,
+        Equipment {
+            slots: HashMap::new(),
         },
     );
 
@@ -93,4 +108,212 @@ pub fn init_player(
             visible: true,
         },
     );
+
+    scene.components.faction.insert(player_id, Faction { kind });
+
+    scene.components.light_source.insert(
+        player_id,
+        LightSource {
+            radius: 4,
+            color: (255, 230, 190),
+            intensity: 1.0,
+            flicker: 0.05,
+        },
+    );
+}
+
+// the free cell closest to being RIVAL_DISTANCE steps away from the nearest
+// player already placed, so a versus game does not start with any two of them
+// side by side
+pub fn rival_spot(scene: &Scene) -> usize {
+    let column_count = scene.game_map.map_info.column_count;
+
+    let starts: Vec<((isize, isize), f64)> = scene
+        .players
+        .iter()
+        .filter_map(|player| scene.components.render.get(player))
+        .map(|render| {
+            (
+                (
+                    (render.index % column_count) as isize,
+                    (render.index / column_count) as isize,
+                ),
+                0.0,
+            )
+        })
+        .collect();
+
+    let dijkstra_map =
+        DijkstraMap::new(&scene.game_map, Movement::default(), &starts);
+
+    let occupied = occupied_cells(&scene.components.render);
+
+    let first = scene
+        .components
+        .render
+        .get(&scene.player)
+        .map_or(0, |render| render.index);
+
+    dijkstra_map
+        .values
+        .iter()
+        .enumerate()
+        .filter(|(index, value)| {
+            value.is_finite()
+                && !occupied.contains(index)
+                && can_enter(&scene.game_map, &Movement::default(), *index)
+        })
+        .min_by(|(_, a), (_, b)| {
+            (*a - RIVAL_DISTANCE)
+                .abs()
+                .partial_cmp(&(*b - RIVAL_DISTANCE).abs())
+                .unwrap_or(Ordering::Equal)
+        })
+        .map_or(first, |(index, _)| index)
+}
+
+// give the player the stats, kit and first ability of a class
+pub fn create_character(
+    scene: &mut Scene,
+    entitys: &mut Entitys,
+    class_index: usize,
+) {
+    let class = &CLASSES[class_index];
+    let player_id = scene.player;
+
+    let components = &mut scene.components;
+
+    components.name.insert(
+        player_id,
+        Name {
+            value: class.name.to_string(),
+        },
+    );
+
+    components
+        .class
+        .insert(player_id, Class { index: class_index });
+
+    components.health.insert(
+        player_id,
+        Health {
+            max_value: class.health,
+            cur_value: class.health,
+        },
+    );
+
+    components.attack.insert(
+        player_id,
+        Attack {
+            power: class.attack,
+        },
+    );
+
+    components.defense.insert(
+        player_id,
+        Defense {
+            armor: class.defense,
+        },
+    );
+
+    components.vision.insert(
+        player_id,
+        Vision {
+            radius: class.vision,
+        },
+    );
+
+    components.experience.insert(
+        player_id,
+        Experience {
+            level: 1,
+            xp: 0,
+            pending: 0,
+        },
+    );
+
+    components.abilities.insert(
+        player_id,
+        Abilities {
+            known: vec![class.ability],
+            cooldowns: HashMap::new(),
+        },
+    );
+
+    // the kit starts off worn
+    for glyph in class.kit.iter() {
+        let item_id =
+            if let Some(item_id) = spawn_carried(components, entitys, *glyph) {
+                item_id
+            } else {
+                continue;
+            };
+
+        if let Some(inventory) = components.inventory.get_mut(&player_id) {
+            inventory.items.push(item_id);
+        }
+
+        let slot = components.equippable.get(&item_id).map(|e| e.slot);
+
+        if let (Some(slot), Some(equipment)) =
+            (slot, components.equipment.get_mut(&player_id))
+        {
+            equipment.slots.insert(slot, item_id);
+        }
+    }
+
+    scene.add_message(format!("you set out as a {}", class.name));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        components::ComponentStore, game_map::test_map, scenes::SceneBuilder,
+    };
+
+    #[test]
+    fn rivals_start_away_from_every_player() {
+        // one long hall, the first player starts at the end of it
+        let row = format!("#{}#", ".".repeat(40));
+        let wall = "#".repeat(42);
+
+        let mut scene = SceneBuilder::new()
+            .set_game_map(test_map(&[&wall, &row, &wall]))
+            .set_components(ComponentStore::default())
+            .build();
+
+        let mut entitys = Entitys::new();
+        let column_count = scene.game_map.map_info.column_count;
+
+        init_player(
+            &mut scene,
+            &mut entitys,
+            1 + column_count,
+            FactionKind::Player,
+        );
+
+        for rival in 1..3 {
+            let spot = rival_spot(&scene);
+
+            init_player(
+                &mut scene,
+                &mut entitys,
+                spot,
+                FactionKind::Rival(rival),
+            );
+        }
+
+        let mut xs: Vec<usize> = scene
+            .players
+            .iter()
+            .map(|player| scene.components.render[player].index % column_count)
+            .collect();
+
+        xs.sort();
+
+        // measured only from the first player the rivals would stand side by
+        // side
+        assert_eq!(xs, vec![1, 13, 25]);
+    }
 }
@@ -1,19 +1,42 @@
+mod ai_states;
+mod animation;
 mod astar;
+mod behavior_tree;
+mod classes;
 mod components;
 pub mod config;
+mod consumables;
+mod dijkstra_map;
 mod entitys;
+mod factions;
 mod fov;
 mod game_map;
+mod hpa;
 mod initialize;
+mod lighting;
+mod los;
 mod map_gen;
+mod morgue;
+mod movement;
+mod net;
+mod rng;
 mod scenes;
+mod state_hash;
+mod stats;
 mod systems;
 mod tileset;
 
 // use std::cell::RefCell;
-use std::error::Error;
+use std::{
+    cmp::Ordering,
+    collections::VecDeque,
+    error::Error,
+    path::Path,
+    time::{Duration, Instant},
+};
 
 use sdl2::{
+    event::Event,
     image::{LoadSurface, Sdl2ImageContext},
     pixels::Color,
     render::Canvas,
@@ -23,15 +46,36 @@ use sdl2::{
     EventPump,
 };
 
-use components::ComponentStore;
+use behavior_tree::load_trees;
+use components::{ComponentStore, FactionKind};
+use config::{GameMode, NetRole};
 use entitys::Entitys;
 use fov::fov;
 use game_map::MapInfo;
+use lighting::lighting;
 use map_gen::generator::{MapGen, MapType};
-use scenes::{SceneBuilder, SceneManager};
-use systems::{ai_system::ai_system, input_system::handle_events};
+use morgue::write_morgue;
+use net::Lockstep;
+use rand::prelude::*;
+use rng::seed_game_rng;
+use scenes::{Scene, SceneBuilder, SceneManager};
+use stats::effective_stats;
+use systems::{
+    input_system::{handle_events, is_action_key},
+    net_system::{local_turn, net_system, record_action, send_key},
+    progression_system::level_up_waiting,
+    status_system::has_status,
+    turn_system::{player_ready, real_time_system, turn_system},
+};
 use tileset::{TileInfo, Tileset};
 
+// how many actions are kept in real time while the player is busy, quitting
+// and the menus are never kept waiting
+const MAX_BUFFERED_INPUT: usize = 2;
+
+// the most ticks run in one frame before the rest are dropped
+const MAX_TICKS_PER_FRAME: usize = 4;
+
 pub struct WindowInfo {
     pub name: String,
     pub width: u32,
@@ -55,18 +99,235 @@ pub struct WorldState<'tex> {
     pub tileset: Tileset<'tex>,
 }
 
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum InventoryAction {
+    Use,
+    Drop,
+    Throw,
+}
+
+// what picking a cell on the map is for
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum TargetAction {
+    // the inventory slot of the item being aimed
+    UseItem(usize),
+    // shoot with whatever ranged weapon the player has
+    Fire,
+    Throw(usize),
+}
+
 #[derive(PartialEq, Debug)]
 pub enum LoopState {
     Run,
     Quit,
     Wait,
+    GameOver,
+    Restart,
+    Inventory(InventoryAction),
+    Targeting(TargetAction),
+    CharacterCreation,
+    LevelUp,
+    CharacterSheet,
+    Abilities,
+}
+
+// put the entitys on to the map and work out what the player can see
+fn update_view(scene: &mut Scene) {
+    for cell in scene.game_map.render_map.iter_mut() {
+        cell.lit = false;
+
+        cell.ent_size = cell.terrain_size;
+        cell.ent_char = cell.terrain_char;
+        if cell.terrain_char != ' ' {
+            cell.visible = true;
+        }
+    }
+
+    // draw the smaller things first so whatever is standing on top of a
+    // corpse is what shows
+    // the players can not see anything that is invisible, but always know
+    // where they are
+    let mut to_draw: Vec<&components::Render> = scene
+        .components
+        .render
+        .iter()
+        .filter(|(ent_id, _)| {
+            scene.is_player(**ent_id)
+                || !has_status(
+                    &scene.components,
+                    **ent_id,
+                    components::StatusKind::Invisibility,
+                )
+        })
+        .map(|(_, render)| render)
+        .collect();
+
+    to_draw
+        .sort_by(|a, b| a.size.partial_cmp(&b.size).unwrap_or(Ordering::Equal));
+
+    for ent in to_draw {
+        let cell = &mut scene.game_map.render_map[ent.index];
+
+        cell.ent_char = ent.reper_char;
+        cell.ent_size = ent.size;
+        cell.visible = ent.visible;
+    }
+
+    // a shared screen shows what any of the players still standing can see,
+    // over the network each machine only shows what its own player sees
+    let players: Vec<usize> = scene
+        .players
+        .iter()
+        .enumerate()
+        .filter(|(i, player)| {
+            scene.local_player.is_none_or(|local| local == *i)
+                && !scene.is_dead(**player)
+        })
+        .map(|(_, player)| *player)
+        .collect();
+
+    for player_id in players {
+        let render_ent = scene.components.render.get(&player_id).unwrap();
+
+        let cx = render_ent.index % scene.game_map.map_info.column_count;
+        let cy = render_ent.index / scene.game_map.map_info.column_count;
+
+        let vision = effective_stats(&scene.components, player_id).vision;
+
+        fov(&mut scene.game_map, (cx, cy), vision);
+    }
+
+    lighting(&mut scene.game_map, &scene.components);
+}
+
+// the player could have died or levelled up since they last acted
+fn end_turn(scene: &mut Scene) {
+    update_view(scene);
+
+    if scene.game_over() {
+        // a versus game ends with a winner on the game over screen instead
+        if !scene.versus() {
+            if let Err(err) = write_morgue(scene) {
+                println!("could not write the morgue file: {}", err);
+            }
+        }
+
+        scene.loop_state = LoopState::GameOver;
+    } else if level_up_waiting(scene) {
+        scene.loop_state = LoopState::LevelUp;
+    } else {
+        scene.loop_state = LoopState::Wait;
+    }
+}
+
+// make a new map with a player on it for everyone playing and throw away the
+// old game if there was one
+fn start_game(
+    world: &mut WorldState,
+    map_info: &MapInfo,
+    player_count: usize,
+    local_player: Option<usize>,
+) {
+    let old_scene = if world.scenes.scenes.is_empty() {
+        None
+    } else {
+        Some(world.scenes.current_scene)
+    };
+
+    let mut components = ComponentStore::default();
+
+    let (game_map, center) = MapGen::new(MapType::Basic, map_info.clone())
+        .make_map(&mut components, &mut world.entitys);
+
+    let scene_builder = SceneBuilder::new()
+        .set_game_map(game_map)
+        .set_components(components);
+
+    let new_scene = world.scenes.register_scene(scene_builder);
+
+    world.scenes.set_current_scene(new_scene);
+
+    if let Some(old_scene) = old_scene {
+        world.scenes.remove_scene(old_scene);
+    }
+
+    let entitys = &mut world.entitys;
+    let scene = world.scenes.get_current_scene_mut();
+
+    let start = center.0 + (scene.game_map.map_info.column_count * center.1);
+
+    initialize::init_player(scene, entitys, start, FactionKind::Player);
+
+    // every player is against all of the others
+    for rival in 1..player_count {
+        let rival_start = initialize::rival_spot(scene);
+
+        initialize::init_player(
+            scene,
+            entitys,
+            rival_start,
+            FactionKind::Rival(rival),
+        );
+    }
+
+    // they are read for every game so they can be worked on without
+    // restarting, monsters fall back on their state graph if this fails
+    match load_trees(Path::new("assets/ai")) {
+        Ok(trees) => scene.behavior_trees = trees,
+        Err(err) => println!("could not load the behavior trees: {}", err),
+    }
+
+    scene.local_player = local_player;
+
+    update_view(scene);
+
+    scene.loop_state = LoopState::CharacterCreation;
 }
 
 pub fn run_game<'tex, 'font>(
     window_info: WindowInfo,
     tile_info: TileInfo,
     map_info: MapInfo,
+    game_mode: GameMode,
+    versus: bool,
+    net_role: Option<NetRole>,
 ) -> Result<(), Box<dyn Error>> {
+    // everyone is told the seed so they all play the same game
+    let (mut net, seed) = match net_role {
+        Some(NetRole::Host { port, players }) => {
+            let (net, seed) = Lockstep::host(port, players)?;
+
+            (Some(net), seed)
+        }
+        Some(NetRole::Join { addr }) => {
+            let (net, seed) = Lockstep::join(&addr)?;
+
+            (Some(net), seed)
+        }
+        None => (None, thread_rng().gen()),
+    };
+
+    seed_game_rng(seed);
+
+    let player_count = match &net {
+        Some(net) => net.players,
+        None if versus => 2,
+        None => 1,
+    };
+
+    // over the network everyone has a screen and keyboard of their own
+    let local_player = net.as_ref().map(|net| net.local);
+
+    // the players would have to share the keyboard at the same time, or
+    // keep their clocks in step over the network
+    let game_mode = if player_count > 1 && game_mode != GameMode::Turns {
+        println!("a game with more then one player is played in turns");
+
+        GameMode::Turns
+    } else {
+        game_mode
+    };
+
     let font_path = "assets/ttf/unscii-16-full.ttf";
 
     let mut ctx = initialize::init_screen(&window_info)?;
@@ -91,34 +352,13 @@ pub fn run_game<'tex, 'font>(
         tileset,
     };
 
-    let mut components = ComponentStore::default();
-
-    let (game_map, center) = MapGen::new(MapType::Basic, map_info)
-        .make_map(&mut components, &mut world.entitys);
-
-    let scene_builder = SceneBuilder::new()
-        .set_game_map(game_map)
-        .set_components(components);
-
-    let new_scene = world.scenes.register_scene(scene_builder);
-
-    world.scenes.set_current_scene(new_scene);
-
-    let entitys = &mut world.entitys;
-    let scene = world.scenes.get_current_scene_mut();
+    start_game(&mut world, &map_info, player_count, local_player);
 
-    initialize::init_player(scene, entitys, center);
+    // in real time the actions pressed while playing wait here for the
+    // player to have the energy to take them
+    let mut input_buffer: VecDeque<Event> = VecDeque::new();
 
-    for ent in scene.components.render.values() {
-        let ent_ind = ent.index;
-        let cell = &mut scene.game_map.render_map[ent_ind];
-
-        cell.ent_size = ent.size;
-        cell.ent_char = ent.reper_char;
-        cell.visible = ent.visible;
-    }
-
-    fov(&mut scene.game_map, center);
+    let mut last_tick = Instant::now();
 
     'main_game: loop {
         ctx.canvas.set_draw_color(Color::RGB(0, 0, 0));
@@ -126,8 +366,34 @@ pub fn run_game<'tex, 'font>(
 
         let scene = world.scenes.get_current_scene_mut();
 
+        let creating = scene.loop_state == LoopState::CharacterCreation;
+
         for evt in ctx.events.poll_iter() {
-            let loop_state = handle_events(scene, &evt);
+            // in a networked game the keys of whoever's turn it is are played
+            // on every machine, anything pressed after they act waits for the
+            // turn to be over
+            if let Some(net) = &net {
+                let ignored = scene.loop_state == LoopState::Run
+                    || !local_turn(scene, net);
+
+                if ignored && !matches!(evt, Event::Quit { .. }) {
+                    continue;
+                }
+            }
+
+            let buffered = matches!(game_mode, GameMode::RealTime { .. })
+                && scene.loop_state == LoopState::Wait
+                && is_action_key(scene, &evt);
+
+            if buffered {
+                if input_buffer.len() < MAX_BUFFERED_INPUT {
+                    input_buffer.push_back(evt);
+                }
+
+                continue;
+            }
+
+            let loop_state = handle_events(scene, &mut world.entitys, &evt);
 
             match loop_state {
                 LoopState::Quit => break 'main_game,
@@ -135,39 +401,103 @@ pub fn run_game<'tex, 'font>(
                     scene.loop_state = loop_state;
                 }
             }
-        }
 
-        if scene.loop_state == LoopState::Run {
-            for cell in scene.game_map.render_map.iter_mut() {
-                cell.lit = false;
+            if let Some(net) = &mut net {
+                if let Err(err) = send_key(net, &evt) {
+                    println!("lost the connection: {}", err);
 
-                cell.ent_size = cell.terrain_size;
-                cell.ent_char = cell.terrain_char;
-                if cell.terrain_char != ' ' {
-                    cell.visible = true;
+                    break 'main_game;
                 }
             }
+        }
 
-            for ent in scene.components.render.values() {
-                let cell = &mut scene.game_map.render_map[ent.index];
+        if let Some(net) = &mut net {
+            if let Err(err) = net_system(scene, &mut world.entitys, net) {
+                println!("lost the connection: {}", err);
 
-                cell.ent_char = ent.reper_char;
-                cell.ent_size = ent.size;
-                cell.visible = ent.visible;
+                break 'main_game;
             }
+        }
+
+        if scene.loop_state == LoopState::Restart {
+            start_game(&mut world, &map_info, player_count, local_player);
+
+            input_buffer.clear();
+
+            continue;
+        }
 
-            let player_id = scene.player;
+        // the class can change how far the player sees
+        if creating && scene.loop_state != LoopState::CharacterCreation {
+            update_view(scene);
+        }
 
-            let render_ent = scene.components.render.get(&player_id).unwrap();
+        match game_mode {
+            GameMode::Turns => {
+                if scene.loop_state == LoopState::Run {
+                    let acting = scene.player;
 
-            let cx = render_ent.index % scene.game_map.map_info.column_count;
-            let cy = render_ent.index / scene.game_map.map_info.column_count;
+                    turn_system(scene, &mut world.entitys);
 
-            fov(&mut scene.game_map, (cx, cy));
+                    end_turn(scene);
 
-            ai_system(scene);
+                    if let Some(net) = &mut net {
+                        if let Err(err) = record_action(scene, net, acting) {
+                            println!("lost the connection: {}", err);
 
-            scene.loop_state = LoopState::Wait;
+                            break 'main_game;
+                        }
+                    }
+                }
+            }
+            GameMode::RealTime { tick_ms } => {
+                let tick = Duration::from_millis(tick_ms);
+
+                let mut ticks = 0;
+
+                // the game is paused while the player is in a menu
+                while (scene.loop_state == LoopState::Wait
+                    || scene.loop_state == LoopState::Run)
+                    && last_tick.elapsed() >= tick
+                    && ticks < MAX_TICKS_PER_FRAME
+                {
+                    last_tick += tick;
+                    ticks += 1;
+
+                    if scene.loop_state == LoopState::Wait
+                        && player_ready(scene)
+                    {
+                        if let Some(evt) = input_buffer.pop_front() {
+                            scene.loop_state =
+                                handle_events(scene, &mut world.entitys, &evt);
+                        }
+                    }
+
+                    if scene.loop_state == LoopState::Quit {
+                        break 'main_game;
+                    }
+
+                    // the input could have opened a menu
+                    if scene.loop_state != LoopState::Wait
+                        && scene.loop_state != LoopState::Run
+                    {
+                        break;
+                    }
+
+                    real_time_system(scene, &mut world.entitys);
+
+                    end_turn(scene);
+                }
+
+                // a slow frame or a menu does not leave a pile of ticks to
+                // catch up on
+                if ticks == MAX_TICKS_PER_FRAME
+                    || (scene.loop_state != LoopState::Wait
+                        && scene.loop_state != LoopState::Run)
+                {
+                    last_tick = Instant::now();
+                }
+            }
         }
 
         scene.render_scene(
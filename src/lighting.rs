@@ -0,0 +1,83 @@
+use rand::prelude::*;
+
+use crate::{
+    components::ComponentStore, fov::visible_cells, game_map::GameMap,
+};
+
+// anything dimmer then this is just dark
+pub const MIN_LIGHT: f64 = 0.05;
+
+// combine every light source in to a light level and color for each cell
+pub fn lighting(game_map: &mut GameMap, components: &ComponentStore) {
+    let mut rng = rand::thread_rng();
+
+    let column_count = game_map.map_info.column_count;
+    let total_count = game_map.render_map.len();
+
+    let mut levels: Vec<f64> = vec![0.0; total_count];
+    let mut colors: Vec<(f64, f64, f64)> = vec![(0.0, 0.0, 0.0); total_count];
+
+    for (ent_id, light) in components.light_source.iter() {
+        let index = if let Some(render) = components.render.get(ent_id) {
+            render.index
+        } else {
+            continue;
+        };
+
+        let flicker = if light.flicker > 0.0 {
+            1.0 - rng.gen_range(0.0, light.flicker)
+        } else {
+            1.0
+        };
+
+        let light_x = (index % column_count) as isize;
+        let light_y = (index / column_count) as isize;
+
+        let view_point = (light_x as usize, light_y as usize);
+
+        for cel_ind in visible_cells(game_map, view_point, light.radius) {
+            let dx = (cel_ind % column_count) as isize - light_x;
+            let dy = (cel_ind / column_count) as isize - light_y;
+
+            let distance = ((dx * dx + dy * dy) as f64).sqrt();
+
+            // fall off to nothing just past the edge of the radius
+            let falloff = 1.0 - (distance / (light.radius as f64 + 1.0));
+
+            let amount = light.intensity * flicker * falloff;
+
+            if amount <= 0.0 {
+                continue;
+            }
+
+            levels[cel_ind] += amount;
+
+            let color = &mut colors[cel_ind];
+            color.0 += light.color.0 as f64 * amount;
+            color.1 += light.color.1 as f64 * amount;
+            color.2 += light.color.2 as f64 * amount;
+        }
+    }
+
+    for (i, cell) in game_map.render_map.iter_mut().enumerate() {
+        let level = levels[i];
+
+        if level <= 0.0 {
+            cell.light_level = 0.0;
+            cell.light_color = (0, 0, 0);
+
+            continue;
+        }
+
+        // the color is the average of all the lights weighted by how much
+        // each one reaches the cell
+        let color = colors[i];
+
+        cell.light_level = level.min(1.0);
+        cell.light_color = (
+            (color.0 / level).min(255.0) as u8,
+            (color.1 / level).min(255.0) as u8,
+            (color.2 / level).min(255.0) as u8,
+        );
+    }
+}
@@ -0,0 +1,218 @@
+use crate::{components::EntitySize, game_map::GameMap};
+
+#[derive(Debug)]
+pub struct LineOfSight {
+    // every cell after the start up to the end or the first blocker
+    pub path: Vec<(isize, isize)>,
+    pub blocker: Option<(isize, isize)>,
+}
+
+impl LineOfSight {
+    // the end can be seen even if it blocks, you can see a wall
+    pub fn reaches(&self, end: (isize, isize)) -> bool {
+        match self.blocker {
+            Some(blocker) => blocker == end,
+            None => true,
+        }
+    }
+}
+
+// all the cells from start to end including both ends
+pub fn bresenham_line(
+    start: (isize, isize),
+    end: (isize, isize),
+) -> Vec<(isize, isize)> {
+    let mut line = vec![];
+
+    let dx = (end.0 - start.0).abs();
+    let dy = -(end.1 - start.1).abs();
+
+    let step_x = if start.0 < end.0 { 1 } else { -1 };
+    let step_y = if start.1 < end.1 { 1 } else { -1 };
+
+    let mut err = dx + dy;
+
+    let (mut x, mut y) = start;
+
+    loop {
+        line.push((x, y));
+
+        if x == end.0 && y == end.1 {
+            break;
+        }
+
+        let err_2 = err * 2;
+
+        if err_2 >= dy {
+            err += dy;
+            x += step_x;
+        }
+
+        if err_2 <= dx {
+            err += dx;
+            y += step_y;
+        }
+    }
+
+    line
+}
+
+// always walk the line from the same end so both directions agree, then flip
+// it if we started from the other side
+pub fn symmetric_line(
+    start: (isize, isize),
+    end: (isize, isize),
+) -> Vec<(isize, isize)> {
+    if start <= end {
+        bresenham_line(start, end)
+    } else {
+        let mut line = bresenham_line(end, start);
+
+        line.reverse();
+
+        line
+    }
+}
+
+// anything off the map blocks as well
+pub fn blocks_sight(game_map: &GameMap, pos: (isize, isize)) -> bool {
+    let column_count = game_map.map_info.column_count as isize;
+    let row_count = game_map.map_info.row_count as isize;
+
+    if pos.0 < 0 || pos.1 < 0 || pos.0 >= column_count || pos.1 >= row_count {
+        return true;
+    }
+
+    let index = (pos.0 + (column_count * pos.1)) as usize;
+
+    game_map.render_map[index].terrain_size > EntitySize::Small
+}
+
+// sight goes along the symmetric line, so a can see b exactly when b can see a
+pub fn line_of_sight(
+    game_map: &GameMap,
+    start: (isize, isize),
+    end: (isize, isize),
+) -> LineOfSight {
+    let line = symmetric_line(start, end);
+
+    let mut path = vec![];
+
+    // skip the start, whoever is looking is standing there
+    for pos in line.into_iter().skip(1) {
+        path.push(pos);
+
+        if blocks_sight(game_map, pos) {
+            return LineOfSight {
+                path,
+                blocker: Some(pos),
+            };
+        }
+    }
+
+    LineOfSight {
+        path,
+        blocker: None,
+    }
+}
+
+// can a thing standing at from see the thing at to
+pub fn can_see(
+    game_map: &GameMap,
+    from: (isize, isize),
+    to: (isize, isize),
+) -> bool {
+    line_of_sight(game_map, from, to).reaches(to)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_map::test_map;
+
+    // the walls are placed so the plain line from one end and the plain line
+    // from the other go through different cells
+    const MAP: [&str; 5] = [
+        "..........",
+        "...#......",
+        "......#...",
+        "..#.......",
+        "........#.",
+    ];
+
+    #[test]
+    fn bresenham_is_not_symmetric() {
+        let mut back = bresenham_line((6, 3), (0, 0));
+
+        back.reverse();
+
+        assert_ne!(bresenham_line((0, 0), (6, 3)), back);
+    }
+
+    #[test]
+    fn symmetric_line_is_the_same_both_ways() {
+        let mut back = symmetric_line((6, 3), (0, 0));
+
+        back.reverse();
+
+        assert_eq!(symmetric_line((0, 0), (6, 3)), back);
+    }
+
+    // sight along the plain line, for comparing against
+    fn plain_sight(
+        game_map: &GameMap,
+        from: (isize, isize),
+        to: (isize, isize),
+    ) -> bool {
+        bresenham_line(from, to)
+            .into_iter()
+            .skip(1)
+            .all(|pos| pos == to || !blocks_sight(game_map, pos))
+    }
+
+    #[test]
+    fn sight_is_symmetric() {
+        let game_map = test_map(&MAP);
+
+        let cells: Vec<(isize, isize)> = (0..5)
+            .flat_map(|y| (0..10).map(move |x| (x, y)))
+            .filter(|pos| !blocks_sight(&game_map, *pos))
+            .collect();
+
+        let mut plain_disagrees = false;
+
+        for a in cells.iter().copied() {
+            for b in cells.iter().copied() {
+                assert_eq!(
+                    can_see(&game_map, a, b),
+                    can_see(&game_map, b, a),
+                    "{:?} {:?}",
+                    a,
+                    b
+                );
+
+                if plain_sight(&game_map, a, b) != plain_sight(&game_map, b, a)
+                {
+                    plain_disagrees = true;
+                }
+            }
+        }
+
+        // the walls have to trip up the plain line for this to mean much
+        assert!(plain_disagrees);
+    }
+
+    #[test]
+    fn the_first_blocker_ends_the_path() {
+        let game_map = test_map(&MAP);
+
+        let line = line_of_sight(&game_map, (0, 1), (9, 1));
+
+        assert_eq!(line.blocker, Some((3, 1)));
+        assert_eq!(line.path, vec![(1, 1), (2, 1), (3, 1)]);
+        assert!(!line.reaches((9, 1)));
+
+        // a wall can be seen itself
+        assert!(line.reaches((3, 1)));
+    }
+}
@@ -2,10 +2,10 @@ use std::cmp::{max, min};
 
 use rand::prelude::*;
 
-use crate::map_gen::generator::MapRect;
+use crate::{map_gen::generator::MapRect, rng::GameRng};
 
 fn new_room(
-    rng: &mut ThreadRng,
+    rng: &mut GameRng,
     min_room: usize,
     max_room: usize,
     column_count: usize,
@@ -22,17 +22,22 @@ fn new_room(
 fn carve_room(room: &MapRect, char_map: &mut Vec<char>, column_count: usize) {
     for x in room.x1..=room.x2 {
         for y in room.y1..=room.y2 {
-            let i = x + (column_count * y);
+            char_map[x + (column_count * y)] = ' ';
+        }
+    }
+}
 
-            if char_map[i] != 'Z' {
-                char_map[i] = ' ';
-            }
+// a small pool in the bottom right corner of a room
+fn carve_pool(room: &MapRect, char_map: &mut [char], column_count: usize) {
+    for x in (room.x2 - 1)..=room.x2 {
+        for y in (room.y2 - 1)..=room.y2 {
+            char_map[x + (column_count * y)] = '~';
         }
     }
 }
 
 fn carve_hallways(
-    rng: &mut ThreadRng,
+    rng: &mut GameRng,
     past_room: &MapRect,
     cur_room: &MapRect,
     char_map: &mut Vec<char>,
@@ -53,27 +58,67 @@ fn carve_hallways(
     let max_x = max(p_center.0, c_center.0);
 
     for x in min_x..=max_x {
-        let i = x + (column_count * sy);
-
-        if char_map[i] != 'Z' {
-            char_map[i] = ' ';
-        }
+        char_map[x + (column_count * sy)] = ' ';
     }
 
     let min_y = min(p_center.1, c_center.1);
     let max_y = max(p_center.1, c_center.1);
 
     for y in min_y..=max_y {
-        let i = sx + (column_count * y);
+        char_map[sx + (column_count * y)] = ' ';
+    }
+}
+
+// only bare floor takes a spawn, so nothing lands in a pool or on another
+fn place(char_map: &mut [char], i: usize, spawn_char: char) {
+    if char_map[i] == ' ' {
+        char_map[i] = spawn_char;
+    }
+}
+
+fn place_spawns(
+    rng: &mut GameRng,
+    room: &MapRect,
+    char_map: &mut [char],
+    column_count: usize,
+) {
+    let center = room.center();
+
+    let i = center.0 + (column_count * center.1);
+
+    if char_map[i] == ' ' && rng.gen_ratio(2, 3) {
+        // mostly undead, with rats and jackals that fight them and the
+        // odd lost villager
+        let monster = *['Z', 'Z', 'A', 'r', 'r', 'j', 'p'].choose(rng).unwrap();
 
-        if char_map[i] != 'Z' {
-            char_map[i] = ' ';
+        place(char_map, i, monster);
+
+        // jackals come in threes
+        if monster == 'j' {
+            place(char_map, i - 1, 'j');
+            place(char_map, i + 1, 'j');
         }
     }
+
+    if rng.gen_ratio(1, 3) {
+        place(char_map, room.x1 + (column_count * room.y1), '*');
+    }
+
+    if rng.gen_ratio(1, 3) {
+        let item = *['!', '!', '?', '/'].choose(rng).unwrap();
+
+        place(char_map, room.x2 + (column_count * room.y1), item);
+    }
+
+    if rng.gen_ratio(1, 5) {
+        let item = *[')', '}', '[', '='].choose(rng).unwrap();
+
+        place(char_map, room.x1 + (column_count * room.y2), item);
+    }
 }
 
 pub fn basic_gen(
-    rng: &mut ThreadRng,
+    rng: &mut GameRng,
     column_count: usize,
     row_count: usize,
     total_tiles: usize,
@@ -108,19 +153,28 @@ pub fn basic_gen(
 
         carve_room(&new_room, &mut char_map, column_count);
 
+        if rng.gen_ratio(1, 4) {
+            carve_pool(&new_room, &mut char_map, column_count);
+        }
+
         let past_room = rooms.last().unwrap();
 
         carve_hallways(rng, past_room, &new_room, &mut char_map, column_count);
 
-        if rng.gen_ratio(2, 3) {
-            let center = new_room.center();
+        rooms.push(new_room);
+    }
 
-            let i = center.0 + (column_count * center.1);
+    // everything is placed once the carving is done so no later room or
+    // hallway can wipe it out. the boss goes first and waits alone in the
+    // room furthest along from the start
+    if rooms.len() > 1 {
+        let center = rooms.last().unwrap().center();
 
-            char_map[i] = 'Z';
-        }
+        char_map[center.0 + (column_count * center.1)] = 'N';
+    }
 
-        rooms.push(new_room);
+    for room in rooms.iter().skip(1) {
+        place_spawns(rng, room, &mut char_map, column_count);
     }
 
     (char_map, rooms.first().unwrap().center())
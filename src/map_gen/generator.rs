@@ -2,7 +2,8 @@ use crate::{
     components::ComponentStore,
     entitys::Entitys,
     game_map::{GameMap, MapInfo},
-    map_gen::basic_dungeon::basic_gen,
+    map_gen::{basic_dungeon::basic_gen, spawner::group_packs},
+    rng::game_rng,
 };
 
 pub struct MapRect {
@@ -53,7 +54,7 @@ impl MapGen {
         components: &mut ComponentStore,
         entitys: &mut Entitys,
     ) -> (GameMap, (usize, usize)) {
-        let mut rng = rand::thread_rng();
+        let mut rng = game_rng();
 
         let mut game_map = GameMap::new(self.map_info.clone());
 
@@ -68,6 +69,75 @@ impl MapGen {
 
         game_map.init_map(components, entitys, sprite_map);
 
+        group_packs(components, game_map.map_info.column_count);
+
         (game_map, center)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        components::Movement, dijkstra_map::DijkstraMap, game_map::Terrain,
+        rng::seed_game_rng,
+    };
+
+    #[test]
+    fn every_spawn_stands_on_floor_it_can_be_walked_to() {
+        for seed in 0..50 {
+            seed_game_rng(seed);
+
+            let map_info = MapInfo {
+                column_count: 40,
+                row_count: 30,
+                total_count: 40 * 30,
+            };
+
+            let mut components = ComponentStore::default();
+
+            let (game_map, center) = MapGen::new(MapType::Basic, map_info)
+                .make_map(&mut components, &mut Entitys::new());
+
+            let start = (center.0 as isize, center.1 as isize);
+
+            let dijkstra_map = DijkstraMap::new(
+                &game_map,
+                Movement::default(),
+                &[(start, 0.0)],
+            );
+
+            assert!(!components.render.is_empty());
+
+            let jackals = components
+                .render
+                .values()
+                .filter(|render| render.reper_char == 'j')
+                .count();
+
+            assert_eq!(jackals % 3, 0, "seed {} split a jackal pack", seed);
+
+            for render in components.render.values() {
+                let column_count = game_map.map_info.column_count;
+                let pos = (
+                    (render.index % column_count) as isize,
+                    (render.index / column_count) as isize,
+                );
+
+                assert_eq!(
+                    game_map.terrain(render.index),
+                    Terrain::Floor,
+                    "seed {} put {} off the floor",
+                    seed,
+                    render.reper_char
+                );
+                assert!(
+                    dijkstra_map.value(pos).is_some(),
+                    "seed {} walled {} in",
+                    seed,
+                    render.reper_char
+                );
+            }
+        }
+    }
+}
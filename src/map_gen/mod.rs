@@ -1,2 +1,3 @@
 pub mod basic_dungeon;
 pub mod generator;
+pub mod spawner;
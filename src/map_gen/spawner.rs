@@ -0,0 +1,556 @@
+use rand::prelude::*;
+
+use crate::{
+    ai_states::{COMPANION, PACK_HUNTER, SENTRY, SHAMBLER, SKULKER, TOWNSFOLK},
+    components::{
+        Ai, AiType, Attack, Charges, ComponentStore, Defense, EntitySize,
+        EquipSlot, Equippable, Faction, FactionKind, Health, Inventory, Item,
+        ItemKind, LightSource, Memory, Name, Pack, Ranged, Render, Speed,
+        Stack, StatBonus, XpReward,
+    },
+    consumables::{consumables_for, CONSUMABLES},
+    entitys::Entitys,
+    rng::game_rng,
+};
+
+// how close pack animals have to start to be in the same pack
+const PACK_RADIUS: isize = 3;
+
+fn add_name(components: &mut ComponentStore, entity_id: usize, name: &str) {
+    components.name.insert(
+        entity_id,
+        Name {
+            value: name.to_string(),
+        },
+    );
+}
+
+fn add_render(
+    components: &mut ComponentStore,
+    entity_id: usize,
+    index: usize,
+    reper_char: char,
+    size: EntitySize,
+) {
+    components.render.insert(
+        entity_id,
+        Render {
+            index,
+            reper_char,
+            size,
+            visible: true,
+        },
+    );
+}
+
+fn add_faction(
+    components: &mut ComponentStore,
+    entity_id: usize,
+    kind: FactionKind,
+) {
+    components.faction.insert(entity_id, Faction { kind });
+}
+
+// a torch sits on the floor so the cell stays empty terrain
+fn spawn_torch(
+    components: &mut ComponentStore,
+    entity_id: usize,
+    index: usize,
+) {
+    add_render(components, entity_id, index, '*', EntitySize::Nothing);
+
+    components.light_source.insert(
+        entity_id,
+        LightSource {
+            radius: 6,
+            color: (255, 160, 60),
+            intensity: 1.0,
+            flicker: 0.2,
+        },
+    );
+}
+
+fn spawn_zombie(
+    components: &mut ComponentStore,
+    entity_id: usize,
+    index: usize,
+) {
+    add_name(components, entity_id, "zombie");
+
+    components.health.insert(
+        entity_id,
+        Health {
+            max_value: 6,
+            cur_value: 6,
+        },
+    );
+
+    components.attack.insert(entity_id, Attack { power: 2 });
+    components.defense.insert(entity_id, Defense { armor: 0 });
+
+    components
+        .ai
+        .insert(entity_id, Ai::new(AiType::Basic, &SHAMBLER, index));
+
+    components
+        .xp_reward
+        .insert(entity_id, XpReward { value: 5 });
+
+    components.memory.insert(entity_id, Memory::new(20));
+
+    components.speed.insert(entity_id, Speed { value: 50 });
+
+    add_faction(components, entity_id, FactionKind::Undead);
+
+    add_render(components, entity_id, index, 'Z', EntitySize::Medium);
+}
+
+// stays back and shoots instead of walking up to the player
+fn spawn_archer(
+    components: &mut ComponentStore,
+    entity_id: usize,
+    index: usize,
+) {
+    add_name(components, entity_id, "skeleton archer");
+
+    components.health.insert(
+        entity_id,
+        Health {
+            max_value: 5,
+            cur_value: 5,
+        },
+    );
+
+    components.attack.insert(entity_id, Attack { power: 1 });
+    components.defense.insert(entity_id, Defense { armor: 0 });
+
+    components
+        .ranged
+        .insert(entity_id, Ranged { power: 2, range: 6 });
+
+    components
+        .ai
+        .insert(entity_id, Ai::new(AiType::Archer, &SENTRY, index));
+
+    components
+        .xp_reward
+        .insert(entity_id, XpReward { value: 8 });
+
+    components.memory.insert(entity_id, Memory::new(15));
+
+    add_faction(components, entity_id, FactionKind::Undead);
+
+    add_render(components, entity_id, index, 'A', EntitySize::Medium);
+}
+
+// weak but it goes for the undead as much as the player
+fn spawn_rat(components: &mut ComponentStore, entity_id: usize, index: usize) {
+    add_name(components, entity_id, "giant rat");
+
+    components.health.insert(
+        entity_id,
+        Health {
+            max_value: 3,
+            cur_value: 3,
+        },
+    );
+
+    components.attack.insert(entity_id, Attack { power: 1 });
+    components.defense.insert(entity_id, Defense { armor: 0 });
+
+    components
+        .ai
+        .insert(entity_id, Ai::new(AiType::Basic, &SKULKER, index));
+
+    components
+        .xp_reward
+        .insert(entity_id, XpReward { value: 2 });
+
+    components.memory.insert(entity_id, Memory::new(5));
+
+    components.speed.insert(entity_id, Speed { value: 120 });
+
+    add_faction(components, entity_id, FactionKind::Beasts);
+
+    add_render(components, entity_id, index, 'r', EntitySize::Small);
+}
+
+// weak on its own, it is always placed with others. it starts in a pack of
+// one and group_packs puts it together with the rest
+fn spawn_jackal(
+    components: &mut ComponentStore,
+    entity_id: usize,
+    index: usize,
+) {
+    add_name(components, entity_id, "jackal");
+
+    components.health.insert(
+        entity_id,
+        Health {
+            max_value: 4,
+            cur_value: 4,
+        },
+    );
+
+    components.attack.insert(entity_id, Attack { power: 2 });
+    components.defense.insert(entity_id, Defense { armor: 0 });
+
+    components
+        .ai
+        .insert(entity_id, Ai::new(AiType::Basic, &PACK_HUNTER, index));
+
+    components
+        .xp_reward
+        .insert(entity_id, XpReward { value: 3 });
+
+    components.memory.insert(entity_id, Memory::new(10));
+
+    components.pack.insert(
+        entity_id,
+        Pack {
+            id: entity_id,
+            strength: 1,
+        },
+    );
+
+    components.speed.insert(entity_id, Speed { value: 150 });
+
+    add_faction(components, entity_id, FactionKind::Beasts);
+
+    add_render(components, entity_id, index, 'j', EntitySize::Small);
+}
+
+// wanders about and never starts a fight
+fn spawn_villager(
+    components: &mut ComponentStore,
+    entity_id: usize,
+    index: usize,
+) {
+    add_name(components, entity_id, "villager");
+
+    components.health.insert(
+        entity_id,
+        Health {
+            max_value: 4,
+            cur_value: 4,
+        },
+    );
+
+    components.defense.insert(entity_id, Defense { armor: 0 });
+
+    components
+        .ai
+        .insert(entity_id, Ai::new(AiType::Basic, &TOWNSFOLK, index));
+
+    add_faction(components, entity_id, FactionKind::Town);
+
+    add_render(components, entity_id, index, 'p', EntitySize::Medium);
+}
+
+// the boss, what it dose is up to its behavior tree. the sentry graph is
+// only used if the tree could not be loaded
+fn spawn_necromancer(
+    components: &mut ComponentStore,
+    entity_id: usize,
+    index: usize,
+) {
+    add_name(components, entity_id, "necromancer");
+
+    components.health.insert(
+        entity_id,
+        Health {
+            max_value: 14,
+            cur_value: 14,
+        },
+    );
+
+    components.attack.insert(entity_id, Attack { power: 2 });
+    components.defense.insert(entity_id, Defense { armor: 1 });
+
+    components
+        .ranged
+        .insert(entity_id, Ranged { power: 3, range: 6 });
+
+    let mut ai = Ai::new(AiType::Archer, &SENTRY, index);
+
+    ai.tree = Some(String::from("necromancer"));
+
+    components.ai.insert(entity_id, ai);
+
+    components
+        .xp_reward
+        .insert(entity_id, XpReward { value: 30 });
+
+    components.memory.insert(entity_id, Memory::new(40));
+
+    add_faction(components, entity_id, FactionKind::Undead);
+
+    add_render(components, entity_id, index, 'N', EntitySize::Medium);
+}
+
+// what the necromancer starts off carrying
+const NECROMANCER_ITEMS: [(&str, usize); 2] =
+    [("scroll of summoning", 2), ("healing potion", 1)];
+
+fn give_items(
+    components: &mut ComponentStore,
+    entitys: &mut Entitys,
+    owner: usize,
+    items: &[(&str, usize)],
+) {
+    let mut carried = vec![];
+
+    for (name, count) in items {
+        let def_index = if let Some(def_index) =
+            CONSUMABLES.iter().position(|def| def.name == *name)
+        {
+            def_index
+        } else {
+            continue;
+        };
+
+        let item_id = entitys.new_id();
+
+        spawn_consumable(components, item_id, 0, def_index);
+
+        components.render.remove(&item_id);
+
+        if let Some(stack) = components.stack.get_mut(&item_id) {
+            stack.count = *count;
+        }
+
+        carried.push(item_id);
+    }
+
+    components.inventory.insert(
+        owner,
+        Inventory {
+            items: carried,
+            capacity: items.len(),
+        },
+    );
+}
+
+// summoned to fight for the player
+fn spawn_wolf(components: &mut ComponentStore, entity_id: usize, index: usize) {
+    add_name(components, entity_id, "spirit wolf");
+
+    components.health.insert(
+        entity_id,
+        Health {
+            max_value: 8,
+            cur_value: 8,
+        },
+    );
+
+    components.attack.insert(entity_id, Attack { power: 3 });
+    components.defense.insert(entity_id, Defense { armor: 1 });
+
+    components
+        .ai
+        .insert(entity_id, Ai::new(AiType::Basic, &COMPANION, index));
+
+    components.memory.insert(entity_id, Memory::new(10));
+
+    components.speed.insert(entity_id, Speed { value: 150 });
+
+    add_faction(components, entity_id, FactionKind::Player);
+
+    add_render(components, entity_id, index, 'w', EntitySize::Medium);
+}
+
+fn spawn_consumable(
+    components: &mut ComponentStore,
+    entity_id: usize,
+    index: usize,
+    def_index: usize,
+) {
+    let def = &CONSUMABLES[def_index];
+
+    add_name(components, entity_id, def.name);
+
+    components.item.insert(
+        entity_id,
+        Item {
+            kind: ItemKind::Consumable(def_index),
+            glyph: def.glyph,
+        },
+    );
+
+    if def.uses > 1 {
+        components
+            .charges
+            .insert(entity_id, Charges { count: def.uses });
+    }
+
+    if def.max_stack > 1 {
+        components.stack.insert(
+            entity_id,
+            Stack {
+                count: 1,
+                max: def.max_stack,
+            },
+        );
+    }
+
+    add_render(components, entity_id, index, def.glyph, EntitySize::Nothing);
+}
+
+fn spawn_equipment(
+    components: &mut ComponentStore,
+    entity_id: usize,
+    index: usize,
+    glyph: char,
+) {
+    let (name, slot, bonus) = match glyph {
+        ')' => (
+            "short sword",
+            EquipSlot::Weapon,
+            StatBonus {
+                attack: 2,
+                ..StatBonus::default()
+            },
+        ),
+        '}' => ("short bow", EquipSlot::Weapon, StatBonus::default()),
+        '[' => (
+            "leather armor",
+            EquipSlot::Armor,
+            StatBonus {
+                defense: 1,
+                speed: -10,
+                ..StatBonus::default()
+            },
+        ),
+        _ => (
+            "ring of sight",
+            EquipSlot::Ring,
+            StatBonus {
+                vision: 2,
+                ..StatBonus::default()
+            },
+        ),
+    };
+
+    add_name(components, entity_id, name);
+
+    components.item.insert(
+        entity_id,
+        Item {
+            kind: ItemKind::Equipment,
+            glyph,
+        },
+    );
+
+    components
+        .equippable
+        .insert(entity_id, Equippable { slot, bonus });
+
+    if glyph == '}' {
+        components
+            .ranged
+            .insert(entity_id, Ranged { power: 3, range: 7 });
+    }
+
+    add_render(components, entity_id, index, glyph, EntitySize::Nothing);
+}
+
+// make whatever entity a map char stands for and give back its id, none if
+// the char is just terrain
+pub fn spawn(
+    components: &mut ComponentStore,
+    entitys: &mut Entitys,
+    map_char: char,
+    index: usize,
+) -> Option<usize> {
+    let entity_id = match map_char {
+        '*' | 'Z' | 'A' | 'r' | 'j' | 'p' | 'w' | 'N' | '!' | '?' | '/'
+        | ')' | '}' | '[' | '=' => entitys.new_id(),
+        _ => return None,
+    };
+
+    match map_char {
+        '*' => spawn_torch(components, entity_id, index),
+        'Z' => spawn_zombie(components, entity_id, index),
+        '!' | '?' | '/' => {
+            let def_index =
+                *consumables_for(map_char).choose(&mut game_rng()).unwrap();
+
+            spawn_consumable(components, entity_id, index, def_index)
+        }
+        'A' => spawn_archer(components, entity_id, index),
+        'r' => spawn_rat(components, entity_id, index),
+        'j' => spawn_jackal(components, entity_id, index),
+        'p' => spawn_villager(components, entity_id, index),
+        'w' => spawn_wolf(components, entity_id, index),
+        'N' => {
+            spawn_necromancer(components, entity_id, index);
+
+            give_items(components, entitys, entity_id, &NECROMANCER_ITEMS);
+        }
+        _ => spawn_equipment(components, entity_id, index, map_char),
+    }
+
+    Some(entity_id)
+}
+
+// pack animals that were placed close to each other become one pack, the
+// pack takes the lowest id of its members
+pub fn group_packs(components: &mut ComponentStore, column_count: usize) {
+    let column_count = column_count as isize;
+
+    let mut members: Vec<(usize, (isize, isize))> = components
+        .pack
+        .keys()
+        .filter_map(|ent_id| {
+            let index = components.render.get(ent_id)?.index as isize;
+
+            Some((*ent_id, (index % column_count, index / column_count)))
+        })
+        .collect();
+
+    members.sort_unstable();
+
+    let mut packs: Vec<Vec<(usize, (isize, isize))>> = vec![];
+
+    for member in members {
+        let close = packs.iter_mut().find(|pack| {
+            pack.iter().any(|(_, pos)| {
+                (pos.0 - member.1 .0).abs().max((pos.1 - member.1 .1).abs())
+                    <= PACK_RADIUS
+            })
+        });
+
+        if let Some(pack) = close {
+            pack.push(member);
+        } else {
+            packs.push(vec![member]);
+        }
+    }
+
+    for pack in packs {
+        let id = pack[0].0;
+
+        for (ent_id, _) in pack.iter() {
+            components.pack.insert(
+                *ent_id,
+                Pack {
+                    id,
+                    strength: pack.len(),
+                },
+            );
+        }
+    }
+}
+
+// an item that starts off carried instead of on the floor
+pub fn spawn_carried(
+    components: &mut ComponentStore,
+    entitys: &mut Entitys,
+    map_char: char,
+) -> Option<usize> {
+    let item_id = spawn(components, entitys, map_char, 0)?;
+
+    components.render.remove(&item_id);
+
+    Some(item_id)
+}
@@ -0,0 +1,77 @@
+use std::{
+    error::Error,
+    fs::File,
+    io::Write,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::scenes::Scene;
+
+// the map the way the player last saw it, anything that was never seen is
+// left blank
+fn map_text(scene: &Scene) -> String {
+    let column_count = scene.game_map.map_info.column_count;
+
+    let mut text = String::new();
+
+    for (i, cell) in scene.game_map.render_map.iter().enumerate() {
+        if i > 0 && i % column_count == 0 {
+            text.push('\n');
+        }
+
+        if cell.visited {
+            text.push(cell.ent_char);
+        } else {
+            text.push(' ');
+        }
+    }
+
+    text.push('\n');
+
+    text
+}
+
+// write a plain text record of the game once the player dies
+pub fn write_morgue(scene: &Scene) -> Result<(), Box<dyn Error>> {
+    let time = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+    let name = if let Some(name) = scene.components.name.get(&scene.player) {
+        name.value.clone()
+    } else {
+        String::from("unknown")
+    };
+
+    let cause = match &scene.death_cause {
+        Some(cause) => cause.clone(),
+        None => String::from("died"),
+    };
+
+    let mut text = String::new();
+
+    text.push_str("sprite fight morgue\n\n");
+    text.push_str(&format!("name: {}\n", name));
+    if let Some(experience) = scene.components.experience.get(&scene.player) {
+        text.push_str(&format!("level: {}\n", experience.level));
+    }
+
+    text.push_str(&format!("{}\n", cause));
+    text.push_str(&format!("depth: {}\n", scene.depth));
+    text.push_str(&format!("turns: {}\n", scene.turns));
+    text.push_str(&format!("kills: {}\n", scene.kills));
+
+    text.push_str("\nfinal map\n\n");
+    text.push_str(&map_text(scene));
+
+    text.push_str("\nmessages\n\n");
+
+    for message in scene.messages.iter() {
+        text.push_str(message);
+        text.push('\n');
+    }
+
+    let mut file = File::create(format!("morgue-{}.txt", time))?;
+
+    file.write_all(text.as_bytes())?;
+
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use std::collections::HashSet;
+
+use crate::{
+    components::{
+        ComponentStore, EntitySize, Movement, MovementMode, Render, Store,
+    },
+    game_map::{GameMap, Terrain},
+};
+
+// what it costs to move in to a cell made of terrain, none means it cant be
+// entered at all
+pub fn terrain_cost(movement: &Movement, terrain: Terrain) -> Option<usize> {
+    match (movement.mode, terrain) {
+        (MovementMode::Walk, Terrain::Floor) => Some(1),
+        (MovementMode::Walk, Terrain::Water) => Some(3),
+        (MovementMode::Swim, Terrain::Floor) => Some(2),
+        (MovementMode::Swim, Terrain::Water) => Some(1),
+        (MovementMode::Fly, Terrain::Floor) => Some(1),
+        (MovementMode::Fly, Terrain::Water) => Some(1),
+        (MovementMode::Phase, Terrain::Wall) => Some(3),
+        (MovementMode::Phase, _) => Some(1),
+        (_, Terrain::Wall) => None,
+    }
+}
+
+pub fn in_bounds(game_map: &GameMap, pos: (isize, isize)) -> bool {
+    pos.0 >= 0
+        && pos.1 >= 0
+        && pos.0 < game_map.map_info.column_count as isize
+        && pos.1 < game_map.map_info.row_count as isize
+}
+
+pub fn can_enter(
+    game_map: &GameMap,
+    movement: &Movement,
+    index: usize,
+) -> bool {
+    terrain_cost(movement, game_map.terrain(index)).is_some()
+}
+
+// the cost of a single step from one cell to a cell next to it, this only
+// looks at the terrain and not at who is standing where
+pub fn step_cost(
+    game_map: &GameMap,
+    movement: &Movement,
+    from: (isize, isize),
+    to: (isize, isize),
+) -> Option<usize> {
+    if !in_bounds(game_map, to) {
+        return None;
+    }
+
+    let column_count = game_map.map_info.column_count as isize;
+
+    let cost = terrain_cost(
+        movement,
+        game_map.terrain((to.0 + column_count * to.1) as usize),
+    )?;
+
+    // a diagonal step needs both of the cells it passes between to be open
+    // unless the entity can squeeze past corners
+    if from.0 != to.0 && from.1 != to.1 && !movement.cut_corners {
+        let corner_a = (from.0 + column_count * to.1) as usize;
+        let corner_b = (to.0 + column_count * from.1) as usize;
+
+        if !can_enter(game_map, movement, corner_a)
+            || !can_enter(game_map, movement, corner_b)
+        {
+            return None;
+        }
+    }
+
+    Some(cost)
+}
+
+// entitys without a movement component just walk
+pub fn movement_of(components: &ComponentStore, entity_id: usize) -> Movement {
+    components
+        .movement
+        .get(&entity_id)
+        .copied()
+        .unwrap_or_default()
+}
+
+// every cell that has an entity in it that takes up room
+pub fn occupied_cells(render: &Store<Render>) -> HashSet<usize> {
+    render
+        .values()
+        .filter(|ent| ent.size > EntitySize::Nothing)
+        .map(|ent| ent.index)
+        .collect()
+}
@@ -0,0 +1,453 @@
+// playing one game on more then one machine over tcp. every machine runs the
+// whole game from the same seed, so all that has to be sent is the keys the
+// player whose turn it is presses. the host passes on what each of the others
+// sends to everyone else, and after every action the one who took it sends a
+// hash of the game so the others can tell if theirs has drifted apart.
+//
+// messages are lines of text
+//
+//   start SEED PLAYERS YOU    from the host once everyone has joined
+//   key PLAYER KEYCODE        a key pressed by a player on their turn
+//   hash PLAYER ACTION HASH   the state after a player's action
+
+use std::{
+    collections::VecDeque,
+    error::Error,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::mpsc::{channel, Receiver, Sender, TryRecvError},
+    thread,
+};
+
+use rand::prelude::*;
+
+// how many of the latest hashes are kept, a hash from another machine can
+// come in after this one has already moved on to the next action
+const HASH_HISTORY: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Message {
+    Start {
+        seed: u64,
+        players: usize,
+        you: usize,
+    },
+    Key {
+        player: usize,
+        keycode: i32,
+    },
+    Hash {
+        player: usize,
+        action: u64,
+        hash: u64,
+    },
+}
+
+impl Message {
+    fn to_line(self) -> String {
+        match self {
+            Message::Start { seed, players, you } => {
+                format!("start {} {} {}\n", seed, players, you)
+            }
+            Message::Key { player, keycode } => {
+                format!("key {} {}\n", player, keycode)
+            }
+            Message::Hash {
+                player,
+                action,
+                hash,
+            } => format!("hash {} {} {}\n", player, action, hash),
+        }
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        let number = |i: usize| -> Option<u64> { words.get(i)?.parse().ok() };
+
+        let message = match *words.first()? {
+            "start" => Message::Start {
+                seed: number(1)?,
+                players: number(2)? as usize,
+                you: number(3)? as usize,
+            },
+            "key" => Message::Key {
+                player: number(1)? as usize,
+                keycode: words.get(2)?.parse().ok()?,
+            },
+            "hash" => Message::Hash {
+                player: number(1)? as usize,
+                action: number(2)?,
+                hash: number(3)?,
+            },
+            _ => return None,
+        };
+
+        Some(message)
+    }
+}
+
+// what comes in from one of the connections, tagged with which one it was
+type Incoming = (usize, Result<Message, String>);
+
+// read lines off a connection on their own thread so the game never waits on
+// the network between frames
+fn spawn_reader(peer: usize, stream: TcpStream, sender: Sender<Incoming>) {
+    let reader = BufReader::new(stream);
+
+    thread::spawn(move || {
+        for line in reader.lines() {
+            let incoming = match line {
+                Ok(line) => match Message::from_line(&line) {
+                    Some(message) => Ok(message),
+                    None => Err(format!("could not read {:?}", line)),
+                },
+                Err(err) => Err(err.to_string()),
+            };
+
+            let failed = incoming.is_err();
+
+            if sender.send((peer, incoming)).is_err() || failed {
+                return;
+            }
+        }
+
+        let _ = sender.send((peer, Err(String::from("the connection closed"))));
+    });
+}
+
+pub struct Lockstep {
+    // which of the players this machine is playing
+    pub local: usize,
+    pub players: usize,
+    // the host has a connection to everyone else, the others only to the host
+    peers: Vec<TcpStream>,
+    is_host: bool,
+    incoming: Receiver<Incoming>,
+    // messages that have come in but have not been used yet
+    queue: VecDeque<Message>,
+    // how many actions have been taken, the hashes are matched up by it
+    pub actions: u64,
+    // the hash of this machine's game after each of the latest actions
+    hashes: VecDeque<(u64, u64)>,
+}
+
+impl Lockstep {
+    // wait for everyone else to join and then start them all on the same seed
+    pub fn host(
+        port: u16,
+        players: usize,
+    ) -> Result<(Self, u64), Box<dyn Error>> {
+        Self::host_on(TcpListener::bind(("0.0.0.0", port))?, players)
+    }
+
+    fn host_on(
+        listener: TcpListener,
+        players: usize,
+    ) -> Result<(Self, u64), Box<dyn Error>> {
+        let port = listener.local_addr()?.port();
+
+        let (sender, incoming) = channel();
+
+        let mut peers = vec![];
+
+        while peers.len() + 1 < players {
+            println!(
+                "waiting for {} more on port {}",
+                players - 1 - peers.len(),
+                port
+            );
+
+            let (stream, addr) = listener.accept()?;
+
+            stream.set_nodelay(true)?;
+
+            println!("{} joined", addr);
+
+            spawn_reader(peers.len(), stream.try_clone()?, sender.clone());
+
+            peers.push(stream);
+        }
+
+        let seed = thread_rng().gen();
+
+        let mut lockstep = Self {
+            local: 0,
+            players,
+            peers,
+            is_host: true,
+            incoming,
+            queue: VecDeque::new(),
+            actions: 0,
+            hashes: VecDeque::new(),
+        };
+
+        for i in 0..lockstep.peers.len() {
+            let start = Message::Start {
+                seed,
+                players,
+                you: i + 1,
+            };
+
+            lockstep.peers[i].write_all(start.to_line().as_bytes())?;
+        }
+
+        Ok((lockstep, seed))
+    }
+
+    // connect to a host and wait for it to start the game
+    pub fn join(addr: &str) -> Result<(Self, u64), Box<dyn Error>> {
+        let stream = TcpStream::connect(addr)?;
+
+        stream.set_nodelay(true)?;
+
+        println!("joined {}, waiting for the game to start", addr);
+
+        let (sender, incoming) = channel();
+
+        spawn_reader(0, stream.try_clone()?, sender);
+
+        let (seed, players, you) = match incoming.recv()? {
+            (_, Ok(Message::Start { seed, players, you })) => {
+                (seed, players, you)
+            }
+            (_, Ok(message)) => {
+                return Err(format!("expected start, got {:?}", message).into())
+            }
+            (_, Err(err)) => return Err(err.into()),
+        };
+
+        let lockstep = Self {
+            local: you,
+            players,
+            peers: vec![stream],
+            is_host: false,
+            incoming,
+            queue: VecDeque::new(),
+            actions: 0,
+            hashes: VecDeque::new(),
+        };
+
+        Ok((lockstep, seed))
+    }
+
+    // send to every connection apart from the one it came from
+    fn send_except(
+        &mut self,
+        message: Message,
+        except: Option<usize>,
+    ) -> Result<(), Box<dyn Error>> {
+        let line = message.to_line();
+
+        for (i, peer) in self.peers.iter_mut().enumerate() {
+            if Some(i) != except {
+                peer.write_all(line.as_bytes())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn send(&mut self, message: Message) -> Result<(), Box<dyn Error>> {
+        self.send_except(message, None)
+    }
+
+    // keep the hash of the game after the action that was just taken
+    pub fn record_hash(&mut self, hash: u64) {
+        if self.hashes.len() == HASH_HISTORY {
+            self.hashes.pop_front();
+        }
+
+        self.hashes.push_back((self.actions, hash));
+    }
+
+    // did this machine's game look the same after that action
+    pub fn in_step(&self, action: u64, hash: u64) -> bool {
+        self.hashes.contains(&(action, hash))
+    }
+
+    // the next message from the other players if there is one, the host passes
+    // each one on to everyone else as it comes in
+    pub fn next_message(&mut self) -> Result<Option<Message>, Box<dyn Error>> {
+        loop {
+            match self.incoming.try_recv() {
+                Ok((peer, Ok(message))) => {
+                    if self.is_host {
+                        self.send_except(message, Some(peer))?;
+                    }
+
+                    self.queue.push_back(message);
+                }
+                Ok((peer, Err(err))) => {
+                    return Err(format!("player {}: {}", peer + 1, err).into())
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    return Err("lost every connection".into())
+                }
+            }
+        }
+
+        Ok(self.queue.pop_front())
+    }
+}
+
+// a host on a free port on this machine with everyone else joined to it, the
+// host is given back first
+#[cfg(test)]
+pub fn loopback(players: usize) -> Vec<Lockstep> {
+    let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+
+    let addr = listener.local_addr().unwrap().to_string();
+
+    let joining: Vec<_> = (1..players)
+        .map(|_| {
+            let addr = addr.clone();
+
+            thread::spawn(move || {
+                Lockstep::join(&addr).map_err(|e| e.to_string())
+            })
+        })
+        .collect();
+
+    let (host, seed) = Lockstep::host_on(listener, players).unwrap();
+
+    let mut nets = vec![host];
+
+    for join in joining {
+        let (net, join_seed) = join.join().unwrap().unwrap();
+
+        assert_eq!(join_seed, seed);
+
+        nets.push(net);
+    }
+
+    // they join in any order so put them in the order they play in
+    nets.sort_by_key(|net| net.local);
+
+    nets
+}
+
+// the next message that comes in, the readers are on their own threads so it
+// can take a moment
+#[cfg(test)]
+pub fn wait_for_message(net: &mut Lockstep) -> Message {
+    for _ in 0..500 {
+        if let Some(message) = net.next_message().unwrap() {
+            return message;
+        }
+
+        thread::sleep(std::time::Duration::from_millis(10));
+    }
+
+    panic!("nothing came in");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_survive_a_round_trip() {
+        let messages = [
+            Message::Start {
+                seed: u64::MAX,
+                players: 3,
+                you: 2,
+            },
+            Message::Key {
+                player: 1,
+                keycode: -7,
+            },
+            Message::Hash {
+                player: 0,
+                action: 12,
+                hash: 1 << 63,
+            },
+        ];
+
+        for message in messages.iter() {
+            let line = message.to_line();
+
+            assert!(line.ends_with('\n'));
+            assert_eq!(Message::from_line(&line), Some(*message));
+        }
+    }
+
+    #[test]
+    fn malformed_lines_are_rejected() {
+        let lines = [
+            "",
+            "   ",
+            "jump 1 2",
+            "key",
+            "key 1",
+            "key one 2",
+            "key 1 up",
+            "start 1 2",
+            "start -1 2 1",
+            "hash 1 2",
+            "hash 1 2 x",
+        ];
+
+        for line in lines.iter() {
+            assert_eq!(Message::from_line(line), None, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn keys_reach_every_other_player() {
+        let mut nets = loopback(3);
+
+        assert_eq!(
+            nets.iter().map(|net| net.local).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert!(nets.iter().all(|net| net.players == 3));
+
+        // the host sends straight to everyone
+        let from_host = Message::Key {
+            player: 0,
+            keycode: 42,
+        };
+
+        nets[0].send(from_host).unwrap();
+
+        assert_eq!(wait_for_message(&mut nets[1]), from_host);
+        assert_eq!(wait_for_message(&mut nets[2]), from_host);
+
+        // the others only have the host, who passes it on as it reads it
+        let from_player = Message::Key {
+            player: 1,
+            keycode: 7,
+        };
+
+        nets[1].send(from_player).unwrap();
+
+        assert_eq!(wait_for_message(&mut nets[0]), from_player);
+        assert_eq!(wait_for_message(&mut nets[2]), from_player);
+
+        // and nobody gets back what they sent
+        thread::sleep(std::time::Duration::from_millis(50));
+
+        assert_eq!(nets[1].next_message().unwrap(), None);
+    }
+
+    #[test]
+    fn hashes_are_matched_by_action() {
+        let mut net = loopback(2).remove(1);
+
+        for hash in 1..=HASH_HISTORY as u64 + 1 {
+            net.actions += 1;
+            net.record_hash(hash * 10);
+        }
+
+        assert!(net.in_step(net.actions, net.actions * 10));
+        // one that comes in late is still matched
+        assert!(net.in_step(net.actions - 1, (net.actions - 1) * 10));
+        assert!(!net.in_step(net.actions, 1));
+        assert!(!net.in_step(net.actions - 1, net.actions * 10));
+        // too old to check against
+        assert!(!net.in_step(1, 10));
+    }
+}
@@ -0,0 +1,39 @@
+// the random numbers the game runs on. they come from a seed so two machines
+// given the same seed play out the same game, things that are only for show
+// like the flicker of a torch still use thread_rng
+use std::cell::RefCell;
+
+use rand::{rngs::StdRng, Error, RngCore, SeedableRng};
+
+thread_local! {
+    static GAME_RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+pub fn seed_game_rng(seed: u64) {
+    GAME_RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+// a handle on the game rng that is used just like thread_rng
+pub struct GameRng;
+
+pub fn game_rng() -> GameRng {
+    GameRng
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        GAME_RNG.with(|rng| rng.borrow_mut().next_u32())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        GAME_RNG.with(|rng| rng.borrow_mut().next_u64())
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        GAME_RNG.with(|rng| rng.borrow_mut().fill_bytes(dest))
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        GAME_RNG.with(|rng| rng.borrow_mut().try_fill_bytes(dest))
+    }
+}
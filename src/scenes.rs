@@ -1,20 +1,108 @@
 use std::collections::HashMap;
 use std::error::Error;
+use std::rc::Rc;
 
 use sdl2::{
     pixels::Color,
     rect::Rect,
-    render::Canvas,
-    render::TextureCreator,
+    render::{BlendMode, Canvas, TextureCreator},
     ttf::Font,
     video::{Window, WindowContext},
 };
 
 use crate::{
-    components::ComponentStore, game_map::GameMap, tileset::Tileset, LoopState,
-    WindowInfo,
+    animation::Projectile,
+    behavior_tree::Node,
+    classes::{ability_def, CLASSES},
+    components::{ComponentStore, Movement},
+    game_map::{GameMap, MapChange},
+    hpa::PathGraph,
+    lighting::MIN_LIGHT,
+    los::line_of_sight,
+    stats::effective_stats,
+    systems::{
+        ai_system::invalidate_paths,
+        item_system::{is_equipped, item_label},
+        noise_system::Noise,
+        progression_system::{choice_label, level_up_choices, xp_to_next},
+        status_system::status_name,
+        target_system::{target_range, valid_target},
+        turn_system::ACTION_COST,
+    },
+    tileset::Tileset,
+    InventoryAction, LoopState, TargetAction, WindowInfo,
 };
 
+// how many of the latest messages are shown under the health bar
+const SHOWN_MESSAGES: usize = 10;
+
+// draw a line of text and give back how tall it was
+fn render_text(
+    texture_creator: &TextureCreator<WindowContext>,
+    canvas: &mut Canvas<Window>,
+    font: &mut Font,
+    text: &str,
+    color: Color,
+    x: i32,
+    y: i32,
+) -> Result<u32, Box<dyn Error>> {
+    // sdl cant render an empty string
+    if text.is_empty() {
+        return Ok(font.height() as u32);
+    }
+
+    let f_surface = font.render(text).blended(color)?;
+
+    let text_width = f_surface.width();
+    let text_height = f_surface.height();
+
+    let texture = texture_creator.create_texture_from_surface(&f_surface)?;
+
+    canvas.copy(&texture, None, Rect::new(x, y, text_width, text_height))?;
+
+    Ok(text_height)
+}
+
+// a box over the map with some lines of text in it, used for menus and
+// screens like the game over
+fn render_panel(
+    texture_creator: &TextureCreator<WindowContext>,
+    canvas: &mut Canvas<Window>,
+    font: &mut Font,
+    start_x: i32,
+    lines: &[String],
+) -> Result<(), Box<dyn Error>> {
+    let line_height = font.height() + 2;
+
+    let box_height = (lines.len() as i32 * line_height) + 20;
+
+    canvas.set_draw_color(Color::RGB(20, 10, 10));
+    canvas.fill_rect(Rect::new(start_x + 20, 20, 440, box_height as u32))?;
+
+    let mut y = 30;
+
+    for line in lines.iter() {
+        render_text(
+            texture_creator,
+            canvas,
+            font,
+            line,
+            Color::RGB(220, 200, 200),
+            start_x + 30,
+            y,
+        )?;
+
+        y += line_height;
+    }
+
+    Ok(())
+}
+
+// scale a color channel by a light level
+fn shade(channel: u8, level: f64) -> u8 {
+    (channel as f64 * level).min(255.0) as u8
+}
+
 pub struct SceneBuilder {
     game_map: Option<GameMap>,
     components: Option<ComponentStore>,
@@ -53,23 +141,148 @@ impl SceneBuilder {
             panic!("needs components");
         };
 
+        let path_graph = PathGraph::new(&game_map, Movement::default());
+
         Scene {
             components,
             game_map,
+            path_graph,
+            players: Vec::new(),
             player: 0,
             loop_state: LoopState::Wait,
+            messages: Vec::new(),
+            depth: 1,
+            turns: 0,
+            kills: 0,
+            death_cause: None,
+            cursor: 0,
+            projectiles: Vec::new(),
+            behavior_trees: HashMap::new(),
+            noises: Vec::new(),
+            action_cost: ACTION_COST,
+            local_player: None,
         }
     }
 }
 
 pub struct Scene {
     pub game_map: GameMap,
+    pub path_graph: PathGraph,
     pub components: ComponentStore,
+    // everyone being played from the keyboard, in the order they take turns
+    pub players: Vec<usize>,
+    // the one of them whose turn it is
     pub player: usize,
     pub loop_state: LoopState,
+    pub messages: Vec<String>,
+    pub depth: usize,
+    pub turns: usize,
+    pub kills: usize,
+    pub death_cause: Option<String>,
+    // the cell being aimed at when picking a target
+    pub cursor: usize,
+    // things in flight that are still being drawn
+    pub projectiles: Vec<Projectile>,
+    // the behavior trees monsters can use by name
+    pub behavior_trees: HashMap<String, Rc<Node>>,
+    // sounds made since the last time the monsters listened
+    pub noises: Vec<Noise>,
+    // what the action the player just took costs in energy
+    pub action_cost: isize,
+    // which of the players this machine plays in a network game, without one
+    // everyone is at the same screen
+    pub local_player: Option<usize>,
 }
 
 impl Scene {
+    pub fn add_message(&mut self, message: String) {
+        self.messages.push(message);
+    }
+
+    pub fn is_player(&self, entity_id: usize) -> bool {
+        self.players.contains(&entity_id)
+    }
+
+    pub fn is_dead(&self, entity_id: usize) -> bool {
+        match self.components.health.get(&entity_id) {
+            Some(health) => health.cur_value <= 0,
+            None => true,
+        }
+    }
+
+    // a game on your own is over when you die, a versus game once there is
+    // no more then one player left standing
+    pub fn game_over(&self) -> bool {
+        let alive = self
+            .players
+            .iter()
+            .filter(|player| !self.is_dead(**player))
+            .count();
+
+        if self.versus() {
+            alive <= 1
+        } else {
+            alive == 0
+        }
+    }
+
+    pub fn versus(&self) -> bool {
+        self.players.len() > 1
+    }
+
+    // the players take turns at one keyboard, each with their own keys to
+    // move with
+    pub fn shared_keyboard(&self) -> bool {
+        self.local_player.is_none() && self.versus()
+    }
+
+    // the last one standing in a versus game, if there is one
+    pub fn winner(&self) -> Option<usize> {
+        let alive: Vec<usize> = self
+            .players
+            .iter()
+            .copied()
+            .filter(|player| !self.is_dead(*player))
+            .collect();
+
+        match alive[..] {
+            [winner] => Some(winner),
+            _ => None,
+        }
+    }
+
+    // how a player is called on screen, by number when there is more then one
+    pub fn player_label(&self, entity_id: usize) -> String {
+        match self.players.iter().position(|player| *player == entity_id) {
+            Some(i) if self.versus() => format!("player {}", i + 1),
+            _ => String::from("you"),
+        }
+    }
+
+    // let everything that keeps its own state about the map know what changed
+    // since the last time this was called
+    pub fn process_map_changes(&mut self) {
+        let changes: Vec<MapChange> = self.game_map.changes.drain(..).collect();
+
+        if changes.is_empty() {
+            return;
+        }
+
+        invalidate_paths(&mut self.components, &self.game_map, &changes);
+
+        let terrain: Vec<usize> = changes
+            .iter()
+            .filter_map(|change| match change {
+                MapChange::Terrain(index) => Some(*index),
+                _ => None,
+            })
+            .collect();
+
+        if !terrain.is_empty() {
+            self.path_graph.update(&self.game_map, &terrain);
+        }
+    }
+
     fn render_map<'t>(
         &mut self,
         canvas: &mut Canvas<Window>,
@@ -91,8 +304,6 @@ impl Scene {
             tileset.tile_info.height,
         );
 
-        tileset.texture.set_color_mod(250, 250, 250);
-
         for (i, render_cell) in self.game_map.render_map.iter().enumerate() {
             if i % column_count == 0 {
                 x = start_x;
@@ -102,15 +313,39 @@ impl Scene {
             dest_rect.set_x(x);
             dest_rect.set_y(y);
 
-            if render_cell.lit {
-                canvas.set_draw_color(Color::RGB(10, 10, 50));
+            // a cell has to be in view and have some light on it to be seen,
+            // dark cells in the fov stay dark
+            if render_cell.lit && render_cell.light_level > MIN_LIGHT {
+                let level = render_cell.light_level;
+                let (r, g, b) = render_cell.light_color;
+
+                // the base background tinted a little by the light
+                canvas.set_draw_color(Color::RGB(
+                    shade(10, level).saturating_add(shade(r, level * 0.15)),
+                    shade(10, level).saturating_add(shade(g, level * 0.15)),
+                    shade(50, level).saturating_add(shade(b, level * 0.15)),
+                ));
                 canvas.fill_rect(dest_rect)?;
 
+                tileset.texture.set_color_mod(
+                    shade(r, level),
+                    shade(g, level),
+                    shade(b, level),
+                );
+
                 if render_cell.ent_char != ' ' {
                     let char_rect = tileset.get_char(render_cell.ent_char);
 
                     canvas.copy(&tileset.texture, *char_rect, dest_rect)?;
                 }
+            } else if render_cell.visited && render_cell.terrain_char != ' ' {
+                // the walls of places we have been or know about are drawn
+                // dim, whatever is in them now can not be seen
+                tileset.texture.set_color_mod(60, 60, 90);
+
+                let char_rect = tileset.get_char(render_cell.terrain_char);
+
+                canvas.copy(&tileset.texture, *char_rect, dest_rect)?;
             }
 
             x += tile_width;
@@ -119,31 +354,40 @@ impl Scene {
         Ok(())
     }
 
-    fn render_ui(
-        &mut self,
+    // how much health a player has left, gives back how tall the bar was
+    fn render_health_bar(
+        &self,
         texture_creator: &TextureCreator<WindowContext>,
         canvas: &mut Canvas<Window>,
         font: &mut Font,
+        entity_id: usize,
         start_x: i32,
         start_y: i32,
-    ) -> Result<(), Box<dyn Error>> {
-        let text = String::from("health");
+    ) -> Result<u32, Box<dyn Error>> {
+        // the one whose turn it is is marked in a versus game
+        let label = if !self.versus() {
+            String::from("health")
+        } else if entity_id == self.player {
+            format!("> {}", self.player_label(entity_id))
+        } else {
+            self.player_label(entity_id)
+        };
 
-        let f_surface = font.render(&text).blended(Color::RGB(1, 1, 1))?;
+        let f_surface = font.render(&label).blended(Color::RGB(1, 1, 1))?;
 
         let text_width = f_surface.width();
         let text_height = f_surface.height();
 
         let percent =
-            if let Some(health) = self.components.health.get(&self.player) {
+            if let Some(health) = self.components.health.get(&entity_id) {
                 let max = health.max_value;
                 let cur = health.cur_value;
 
-                let percent = (max / cur) * 100;
+                let percent = (cur.max(0) * 100) / max;
 
                 percent as u32
             } else {
-                return Ok(());
+                0
             };
 
         let max_bar_value = 240;
@@ -168,6 +412,104 @@ impl Scene {
 
         canvas.copy(&text, None, text_dist_rect)?;
 
+        Ok(text_height + 4)
+    }
+
+    fn render_ui(
+        &mut self,
+        texture_creator: &TextureCreator<WindowContext>,
+        canvas: &mut Canvas<Window>,
+        font: &mut Font,
+        start_x: i32,
+        start_y: i32,
+    ) -> Result<(), Box<dyn Error>> {
+        // there is nothing to show until a class has been picked
+        if !self.components.health.contains_key(&self.player) {
+            return Ok(());
+        }
+
+        let mut y = start_y;
+
+        // every player gets a bar, the rest of the panel is about whoever's
+        // turn it is
+        for player in self.players.iter() {
+            y += self.render_health_bar(
+                texture_creator,
+                canvas,
+                font,
+                *player,
+                start_x,
+                y,
+            )? as i32
+                + 10;
+        }
+
+        let stats = effective_stats(&self.components, self.player);
+
+        let stat_lines = [
+            format!("attack  {}", stats.attack),
+            format!("defense {}", stats.defense),
+            format!("vision  {}", stats.vision),
+            format!("speed   {}", stats.speed),
+        ];
+
+        for line in stat_lines.iter() {
+            y += render_text(
+                texture_creator,
+                canvas,
+                font,
+                line,
+                Color::RGB(200, 200, 120),
+                start_x,
+                y,
+            )? as i32;
+        }
+
+        if self.components.sneaking.contains_key(&self.player) {
+            y += render_text(
+                texture_creator,
+                canvas,
+                font,
+                "sneaking",
+                Color::RGB(120, 200, 220),
+                start_x,
+                y,
+            )? as i32;
+        }
+
+        if let Some(status) = self.components.status_effects.get(&self.player) {
+            for effect in status.effects.iter() {
+                let line =
+                    format!("{} ({})", status_name(effect.kind), effect.turns);
+
+                y += render_text(
+                    texture_creator,
+                    canvas,
+                    font,
+                    &line,
+                    Color::RGB(120, 200, 220),
+                    start_x,
+                    y,
+                )? as i32;
+            }
+        }
+
+        y += 10;
+
+        let first = self.messages.len().saturating_sub(SHOWN_MESSAGES);
+
+        for message in self.messages[first..].iter() {
+            y += render_text(
+                texture_creator,
+                canvas,
+                font,
+                message,
+                Color::RGB(200, 200, 200),
+                start_x,
+                y,
+            )? as i32;
+        }
+
         Ok(())
     }
 
@@ -186,14 +528,388 @@ impl Scene {
 
         self.render_map(canvas, tileset, column_count, start_x)?;
 
+        self.render_projectiles(canvas, tileset, start_x)?;
+
         let ui_start_x = start_x + (column_count as i32 * tile_width) + 5;
 
         let ui_start_y = 5;
 
         self.render_ui(texture_creator, canvas, font, ui_start_x, ui_start_y)?;
 
+        match self.loop_state {
+            LoopState::GameOver => {
+                self.render_game_over(texture_creator, canvas, font, start_x)?
+            }
+            LoopState::Inventory(action) => self.render_inventory(
+                texture_creator,
+                canvas,
+                font,
+                start_x,
+                action,
+            )?,
+            LoopState::CharacterCreation => render_panel(
+                texture_creator,
+                canvas,
+                font,
+                start_x,
+                &self.character_creation_lines(),
+            )?,
+            LoopState::LevelUp => render_panel(
+                texture_creator,
+                canvas,
+                font,
+                start_x,
+                &self.level_up_lines(),
+            )?,
+            LoopState::CharacterSheet => render_panel(
+                texture_creator,
+                canvas,
+                font,
+                start_x,
+                &self.character_sheet_lines(),
+            )?,
+            LoopState::Abilities => render_panel(
+                texture_creator,
+                canvas,
+                font,
+                start_x,
+                &self.ability_lines(),
+            )?,
+            LoopState::Targeting(action) => {
+                self.render_cursor(canvas, tileset, start_x, action)?
+            }
+            _ => {}
+        }
+
         Ok(())
     }
+
+    // draw everything still in flight where it is now and forget the ones that
+    // have landed, they are only drawn in cells the player can see
+    fn render_projectiles<'t>(
+        &mut self,
+        canvas: &mut Canvas<Window>,
+        tileset: &mut Tileset<'t>,
+        start_x: i32,
+    ) -> Result<(), Box<dyn Error>> {
+        let tile_width = tileset.tile_info.width;
+        let tile_height = tileset.tile_info.height;
+
+        let column_count = self.game_map.map_info.column_count as isize;
+
+        self.projectiles
+            .retain(|projectile| projectile.position().is_some());
+
+        for projectile in self.projectiles.iter() {
+            let pos = if let Some(pos) = projectile.position() {
+                pos
+            } else {
+                continue;
+            };
+
+            let index = (pos.0 + column_count * pos.1) as usize;
+
+            if !self.game_map.render_map[index].lit {
+                continue;
+            }
+
+            let dest_rect = Rect::new(
+                start_x + pos.0 as i32 * tile_width as i32,
+                pos.1 as i32 * tile_height as i32,
+                tile_width,
+                tile_height,
+            );
+
+            canvas.set_draw_color(Color::RGB(0, 0, 0));
+            canvas.fill_rect(dest_rect)?;
+
+            tileset.texture.set_color_mod(255, 255, 200);
+
+            let char_rect = tileset.get_char(projectile.glyph);
+
+            canvas.copy(&tileset.texture, *char_rect, dest_rect)?;
+        }
+
+        Ok(())
+    }
+
+    // the line from the player to the cursor and a box around the cursor,
+    // green if it can be aimed at and red if not
+    fn render_cursor<'t>(
+        &mut self,
+        canvas: &mut Canvas<Window>,
+        tileset: &mut Tileset<'t>,
+        start_x: i32,
+        action: TargetAction,
+    ) -> Result<(), Box<dyn Error>> {
+        let tile_width = tileset.tile_info.width;
+        let tile_height = tileset.tile_info.height;
+
+        let column_count = self.game_map.map_info.column_count as isize;
+
+        let player_index =
+            if let Some(render) = self.components.render.get(&self.player) {
+                render.index as isize
+            } else {
+                return Ok(());
+            };
+
+        let cursor = self.cursor as isize;
+
+        let valid = target_range(self, action).is_some_and(|range| {
+            valid_target(self, self.player, self.cursor, range)
+        });
+
+        let color = if valid {
+            Color::RGB(80, 220, 80)
+        } else {
+            Color::RGB(220, 60, 60)
+        };
+
+        let cell_rect = |pos: (isize, isize)| {
+            Rect::new(
+                start_x + pos.0 as i32 * tile_width as i32,
+                pos.1 as i32 * tile_height as i32,
+                tile_width,
+                tile_height,
+            )
+        };
+
+        let line = line_of_sight(
+            &self.game_map,
+            (player_index % column_count, player_index / column_count),
+            (cursor % column_count, cursor / column_count),
+        );
+
+        canvas.set_blend_mode(BlendMode::Blend);
+        canvas.set_draw_color(Color::RGBA(color.r, color.g, color.b, 60));
+
+        for pos in line.path.iter() {
+            canvas.fill_rect(cell_rect(*pos))?;
+        }
+
+        canvas.set_blend_mode(BlendMode::None);
+        canvas.set_draw_color(color);
+
+        canvas.draw_rect(cell_rect((
+            cursor % column_count,
+            cursor / column_count,
+        )))?;
+
+        Ok(())
+    }
+
+    fn character_creation_lines(&self) -> Vec<String> {
+        let heading = if self.versus() {
+            format!("{}, pick a class", self.player_label(self.player))
+        } else {
+            String::from("pick a class")
+        };
+
+        let mut lines = vec![heading, String::new()];
+
+        for (i, class) in CLASSES.iter().enumerate() {
+            let letter = (b'a' + i as u8) as char;
+
+            lines.push(format!("{}) {}", letter, class.name));
+            lines.push(format!("   {}", class.description));
+            lines.push(format!(
+                "   health {} attack {} defense {}",
+                class.health, class.attack, class.defense
+            ));
+        }
+
+        lines.push(String::new());
+        lines.push(String::from("escape to quit"));
+
+        lines
+    }
+
+    fn level_up_lines(&self) -> Vec<String> {
+        let mut lines = vec![String::from("level up"), String::new()];
+
+        for (i, choice) in level_up_choices(self).iter().enumerate() {
+            let letter = (b'a' + i as u8) as char;
+
+            lines.push(format!("{}) {}", letter, choice_label(*choice)));
+        }
+
+        lines
+    }
+
+    fn character_sheet_lines(&self) -> Vec<String> {
+        let player = self.player;
+        let components = &self.components;
+
+        let name = components
+            .name
+            .get(&player)
+            .map_or(String::from("unknown"), |name| name.value.clone());
+
+        let mut lines = vec![name, String::new()];
+
+        if let Some(experience) = components.experience.get(&player) {
+            lines.push(format!("level   {}", experience.level));
+            lines.push(format!(
+                "xp      {} / {}",
+                experience.xp,
+                xp_to_next(experience.level)
+            ));
+        }
+
+        if let Some(health) = components.health.get(&player) {
+            lines.push(format!(
+                "health  {} / {}",
+                health.cur_value, health.max_value
+            ));
+        }
+
+        let stats = effective_stats(components, player);
+
+        lines.push(format!("attack  {}", stats.attack));
+        lines.push(format!("defense {}", stats.defense));
+        lines.push(format!("vision  {}", stats.vision));
+        lines.push(format!("speed   {}", stats.speed));
+
+        lines.push(String::new());
+        lines.push(String::from("equipped"));
+
+        if let Some(equipment) = components.equipment.get(&player) {
+            let mut worn: Vec<String> = equipment
+                .slots
+                .iter()
+                .map(|(slot, item_id)| {
+                    format!("  {:?}: {}", slot, item_label(self, *item_id))
+                })
+                .collect();
+
+            worn.sort();
+
+            lines.extend(worn);
+        }
+
+        lines.push(String::new());
+        lines.push(String::from("abilities"));
+
+        if let Some(abilities) = components.abilities.get(&player) {
+            for ability in abilities.known.iter() {
+                lines.push(format!("  {}", ability_def(*ability).name));
+            }
+        }
+
+        lines.push(String::new());
+        lines.push(String::from("escape to close"));
+
+        lines
+    }
+
+    fn ability_lines(&self) -> Vec<String> {
+        let mut lines = vec![String::from("use which ability"), String::new()];
+
+        if let Some(abilities) = self.components.abilities.get(&self.player) {
+            for (i, ability) in abilities.known.iter().enumerate() {
+                let letter = (b'a' + i as u8) as char;
+
+                let state = match abilities.cooldowns.get(ability) {
+                    Some(turns) => format!("{} turns", turns),
+                    None => String::from("ready"),
+                };
+
+                lines.push(format!(
+                    "{}) {} ({})",
+                    letter,
+                    ability_def(*ability).name,
+                    state
+                ));
+            }
+        }
+
+        lines.push(String::new());
+        lines.push(String::from("escape to close"));
+
+        lines
+    }
+
+    // covers the map with how the game went
+    fn render_game_over(
+        &mut self,
+        texture_creator: &TextureCreator<WindowContext>,
+        canvas: &mut Canvas<Window>,
+        font: &mut Font,
+        start_x: i32,
+    ) -> Result<(), Box<dyn Error>> {
+        let cause = match &self.death_cause {
+            Some(cause) => cause.clone(),
+            None => String::from("died"),
+        };
+
+        let heading = if !self.versus() {
+            String::from("you have died")
+        } else if let Some(winner) = self.winner() {
+            format!("{} wins", self.player_label(winner))
+        } else {
+            String::from("nobody is left standing")
+        };
+
+        let lines = [
+            heading,
+            String::new(),
+            cause,
+            format!("depth: {}", self.depth),
+            format!("turns: {}", self.turns),
+            format!("kills: {}", self.kills),
+            String::new(),
+            String::from("r to restart, escape to quit"),
+        ];
+
+        render_panel(texture_creator, canvas, font, start_x, &lines)
+    }
+
+    // the list of what the player is carrying
+    fn render_inventory(
+        &mut self,
+        texture_creator: &TextureCreator<WindowContext>,
+        canvas: &mut Canvas<Window>,
+        font: &mut Font,
+        start_x: i32,
+        action: InventoryAction,
+    ) -> Result<(), Box<dyn Error>> {
+        let title = match action {
+            InventoryAction::Use => "use which item",
+            InventoryAction::Drop => "drop which item",
+            InventoryAction::Throw => "throw which item",
+        };
+
+        let mut lines = vec![String::from(title), String::new()];
+
+        if let Some(inventory) = self.components.inventory.get(&self.player) {
+            for (slot, item_id) in inventory.items.iter().enumerate() {
+                let letter = (b'a' + slot as u8) as char;
+
+                let equipped = if is_equipped(self, self.player, *item_id) {
+                    " (equipped)"
+                } else {
+                    ""
+                };
+
+                lines.push(format!(
+                    "{}) {}{}",
+                    letter,
+                    item_label(self, *item_id),
+                    equipped
+                ));
+            }
+        }
+
+        if lines.len() == 2 {
+            lines.push(String::from("you are not carrying anything"));
+        }
+
+        lines.push(String::new());
+        lines.push(String::from("escape to close"));
+
+        render_panel(texture_creator, canvas, font, start_x, &lines)
+    }
 }
 
 pub struct SceneManager {
@@ -220,7 +936,7 @@ impl SceneManager {
     }
 
     pub fn set_current_scene(&mut self, scene_id: usize) {
-        if self.scenes.contains_key(&self.current_scene) {
+        if self.scenes.contains_key(&scene_id) {
             self.current_scene = scene_id;
         } else {
             // TODO: this is probably not what i want
@@ -238,6 +954,10 @@ impl SceneManager {
         return new_id;
     }
 
+    pub fn remove_scene(&mut self, scene_id: usize) -> Option<Scene> {
+        self.scenes.remove(&scene_id)
+    }
+
     pub fn get_current_scene(&self) -> &Scene {
         if let Some(scene) = self.scenes.get(&self.current_scene) {
             scene
@@ -0,0 +1,77 @@
+// a hash of everything about a game that two machines playing it in step have
+// to agree on. it is not all of the state, just enough that a game that has
+// drifted apart shows up within an action or two. what is only for show, like
+// the lighting or what has been seen, is left out
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+use crate::{
+    components::{ComponentStore, Store},
+    game_map::GameMap,
+};
+
+// the entries of a store lowest id first, so the hash does not depend on the
+// order they are kept in
+fn sorted<T>(store: &Store<T>) -> Vec<(&usize, &T)> {
+    let mut entries: Vec<(&usize, &T)> = store.iter().collect();
+
+    entries.sort_unstable_by_key(|(ent_id, _)| **ent_id);
+
+    entries
+}
+
+pub fn state_hash(components: &ComponentStore, game_map: &GameMap) -> u64 {
+    let mut hasher = DefaultHasher::new();
+
+    for (ent_id, render) in sorted(&components.render) {
+        (ent_id, render.index, render.reper_char).hash(&mut hasher);
+    }
+
+    for (ent_id, health) in sorted(&components.health) {
+        (ent_id, health.cur_value, health.max_value).hash(&mut hasher);
+    }
+
+    for (ent_id, energy) in sorted(&components.energy) {
+        (ent_id, energy.value).hash(&mut hasher);
+    }
+
+    for (ent_id, ai) in sorted(&components.ai) {
+        (ent_id, ai.state, ai.state_turns, ai.waypoint).hash(&mut hasher);
+    }
+
+    for (ent_id, memory) in sorted(&components.memory) {
+        (ent_id, memory.last_seen, memory.search_spot).hash(&mut hasher);
+    }
+
+    for (ent_id, status) in sorted(&components.status_effects) {
+        for effect in status.effects.iter() {
+            (ent_id, effect.kind, effect.turns).hash(&mut hasher);
+        }
+    }
+
+    for (ent_id, inventory) in sorted(&components.inventory) {
+        (ent_id, &inventory.items).hash(&mut hasher);
+    }
+
+    for (ent_id, experience) in sorted(&components.experience) {
+        (ent_id, experience.level, experience.xp).hash(&mut hasher);
+    }
+
+    for (ent_id, attack) in sorted(&components.attack) {
+        (ent_id, attack.power).hash(&mut hasher);
+    }
+
+    for (ent_id, defense) in sorted(&components.defense) {
+        (ent_id, defense.armor).hash(&mut hasher);
+    }
+
+    // nothing changes the terrain during a game yet, but two maps that were
+    // made differently show up here
+    for cell in game_map.render_map.iter() {
+        cell.terrain_char.hash(&mut hasher);
+    }
+
+    hasher.finish()
+}
@@ -0,0 +1,95 @@
+use crate::components::{ComponentStore, Ranged, StatusKind};
+
+// how far something can see if it has no vision of its own
+pub const BASE_VISION: isize = 5;
+
+// how fast anything without a speed of its own is before equipment changes it
+pub const BASE_SPEED: isize = 100;
+
+// how much haste adds to speed and slow takes away
+const HASTE_SPEED: isize = 50;
+
+// what sneaking costs in speed
+const SNEAK_SPEED: isize = 40;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Stats {
+    pub attack: isize,
+    pub defense: isize,
+    pub vision: isize,
+    pub speed: isize,
+}
+
+// the base stats of an entity with everything it has equipped added on
+pub fn effective_stats(components: &ComponentStore, entity_id: usize) -> Stats {
+    let mut stats = Stats {
+        attack: components
+            .attack
+            .get(&entity_id)
+            .map_or(0, |attack| attack.power),
+        defense: components
+            .defense
+            .get(&entity_id)
+            .map_or(0, |defense| defense.armor),
+        vision: components
+            .vision
+            .get(&entity_id)
+            .map_or(BASE_VISION, |vision| vision.radius),
+        speed: components
+            .speed
+            .get(&entity_id)
+            .map_or(BASE_SPEED, |speed| speed.value),
+    };
+
+    if let Some(equipment) = components.equipment.get(&entity_id) {
+        for item_id in equipment.slots.values() {
+            if let Some(equippable) = components.equippable.get(item_id) {
+                let bonus = &equippable.bonus;
+
+                stats.attack += bonus.attack;
+                stats.defense += bonus.defense;
+                stats.vision += bonus.vision;
+                stats.speed += bonus.speed;
+            }
+        }
+    }
+
+    if let Some(status) = components.status_effects.get(&entity_id) {
+        for effect in status.effects.iter() {
+            match effect.kind {
+                StatusKind::Haste => stats.speed += HASTE_SPEED,
+                StatusKind::Slow => stats.speed -= HASTE_SPEED,
+                StatusKind::Blindness => stats.vision = 1,
+                _ => {}
+            }
+        }
+    }
+
+    if components.sneaking.contains_key(&entity_id) {
+        stats.speed -= SNEAK_SPEED;
+    }
+
+    // you can always see where you are standing and always move a little
+    stats.vision = stats.vision.max(1);
+    stats.speed = stats.speed.max(10);
+
+    stats
+}
+
+// what an entity can shoot with, its own if it has one and otherwise
+// whatever it has equipped
+pub fn ranged_of(
+    components: &ComponentStore,
+    entity_id: usize,
+) -> Option<Ranged> {
+    if let Some(ranged) = components.ranged.get(&entity_id) {
+        return Some(*ranged);
+    }
+
+    components
+        .equipment
+        .get(&entity_id)?
+        .slots
+        .values()
+        .find_map(|item_id| components.ranged.get(item_id).copied())
+}
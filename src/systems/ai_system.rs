@@ -1,91 +1,971 @@
-use crate::{astar::astar, scenes::Scene};
+use std::collections::{HashMap, HashSet, VecDeque};
 
-use super::move_system::{move_by_system, move_to_system};
+use rand::prelude::*;
 
-#[allow(dead_code)]
-fn basic_movement(scene: &mut Scene, ai_id: usize) {
-    let index = scene.components.render.get(&scene.player).unwrap().index;
+use crate::{
+    ai_states::{AiState, Trigger},
+    components::{
+        AiPath, AiType, ComponentStore, EntitySize, Memory, Movement,
+        StatusKind,
+    },
+    dijkstra_map::DijkstraMap,
+    entitys::Entitys,
+    game_map::{GameMap, MapChange},
+    los::can_see,
+    movement::{can_enter, in_bounds, movement_of, occupied_cells},
+    rng::game_rng,
+    scenes::Scene,
+    stats::effective_stats,
+};
 
-    let ai_pos_index = if let Some(ai_pos) = scene.components.render.get(&ai_id)
+use super::{
+    behavior_system::{run_tree, TreeContext},
+    combat_system::{is_hostile, melee_attack, move_or_attack},
+    move_system::{move_by_system, MoveResult, DIRECTIONS},
+    ranged_system::{clear_shot, fire},
+    status_system::has_status,
+};
+
+// how far the target can move away from the end of a path before the path is
+// no good any more
+const PATH_TOLERANCE: isize = 2;
+
+// archers back away when their target gets closer then this
+const KEEP_DISTANCE: isize = 3;
+
+// allies with nothing to do stay this close to the player
+const FOLLOW_DISTANCE: isize = 2;
+
+// how many times the state can change in one turn
+const MAX_TRANSITIONS: usize = 3;
+
+// an ai is hurt enough to run when it is down to a quarter of its health
+pub fn wants_to_flee(scene: &Scene, ai_id: usize) -> bool {
+    if let Some(health) = scene.components.health.get(&ai_id) {
+        health.cur_value * 4 <= health.max_value
+    } else {
+        false
+    }
+}
+
+// throw away any cached path that goes through a cell that changed
+pub fn invalidate_paths(
+    components: &mut ComponentStore,
+    game_map: &GameMap,
+    changes: &[MapChange],
+) {
+    let column_count = game_map.map_info.column_count as isize;
+
+    let changed: HashSet<(isize, isize)> = changes
+        .iter()
+        .map(|change| match change {
+            MapChange::Terrain(index) | MapChange::Occupancy(index) => {
+                let index = *index as isize;
+
+                (index % column_count, index / column_count)
+            }
+        })
+        .collect();
+
+    components.ai_path.retain(|_, ai_path| {
+        !ai_path.steps.iter().any(|s| changed.contains(s))
+    });
+}
+
+// take the next step on the cached path, returns false if there is no usable
+// path so a new one needs to be made
+fn follow_path(
+    scene: &mut Scene,
+    ai_id: usize,
+    pos: (isize, isize),
+    target: (isize, isize),
+) -> bool {
+    let next = if let Some(ai_path) = scene.components.ai_path.get(&ai_id) {
+        if distance(ai_path.goal, target) > PATH_TOLERANCE {
+            scene.components.ai_path.remove(&ai_id);
+
+            return false;
+        }
+
+        ai_path.steps.front().copied()
+    } else {
+        return false;
+    };
+
+    // the path ends next to where the target was, stepping in to it is an
+    // attack so the path is kept for as long as the target stays in reach
+    let next = if let Some(next) = next {
+        next
+    } else if pos != target && distance(pos, target) <= 1 {
+        return move_or_attack(scene, ai_id, target);
+    } else {
+        scene.components.ai_path.remove(&ai_id);
+
+        return false;
+    };
+
+    if move_or_attack(scene, ai_id, next) {
+        if let Some(ai_path) = scene.components.ai_path.get_mut(&ai_id) {
+            ai_path.steps.pop_front();
+        }
+
+        true
+    } else {
+        // someone is in the way
+        scene.components.ai_path.remove(&ai_id);
+
+        false
+    }
+}
+
+// a confused ai has no say in where it goes, move_by_system picks a random
+// direction and it attacks whatever it walks in to
+fn stumble(scene: &mut Scene, ai_id: usize) {
+    let result = move_by_system(
+        &mut scene.game_map,
+        &mut scene.components,
+        ai_id,
+        (0, 0),
+    );
+
+    if let MoveResult::Bump(target) = result {
+        if is_hostile(scene, ai_id, target) {
+            melee_attack(scene, ai_id, target);
+        }
+    }
+}
+
+// the closest thing the ai can see that it is hostile to
+fn pick_target(
+    scene: &Scene,
+    ai_id: usize,
+    pos: (isize, isize),
+) -> Option<(usize, (isize, isize))> {
+    let column_count = scene.game_map.map_info.column_count as isize;
+
+    let vision = effective_stats(&scene.components, ai_id).vision;
+
+    let mut best: Option<(isize, usize, (isize, isize))> = None;
+
+    for (ent_id, render) in scene.components.render.iter() {
+        if *ent_id == ai_id
+            || render.size == EntitySize::Nothing
+            || !is_hostile(scene, ai_id, *ent_id)
+            || has_status(&scene.components, *ent_id, StatusKind::Invisibility)
+        {
+            continue;
+        }
+
+        let target_pos = (
+            render.index as isize % column_count,
+            render.index as isize / column_count,
+        );
+
+        let distance =
+            (target_pos.0 - pos.0).pow(2) + (target_pos.1 - pos.1).pow(2);
+
+        if distance >= vision.pow(2)
+            || !can_see(&scene.game_map, pos, target_pos)
+        {
+            continue;
+        }
+
+        // ties go to the lowest id so the choice dose not depend on the
+        // order of the hash map
+        let closer = match best {
+            Some((best_distance, best_id, _)) => {
+                (distance, *ent_id) < (best_distance, best_id)
+            }
+            None => true,
+        };
+
+        if closer {
+            best = Some((distance, *ent_id, target_pos));
+        }
+    }
+
+    best.map(|(_, ent_id, target_pos)| (ent_id, target_pos))
+}
+
+// how close an ai has to get to a cell for it to count as having got there,
+// something else could be standing in it
+pub const ARRIVE_DISTANCE: isize = 1;
+
+// how far from where the target was last seen a search goes
+const SEARCH_RADIUS: isize = 4;
+
+// how much a fleeing ai cares about heading home compared to getting away
+const HOME_PULL: f64 = 0.3;
+
+// a patrol is made of cells this close to home that can be seen from it
+const PATROL_RADIUS: isize = 6;
+const PATROL_POINTS: usize = 3;
+
+// the maps are kept for the turn by how the ai moves and where the goal is
+pub type MapCache = HashMap<(Movement, (isize, isize)), DijkstraMap>;
+
+pub fn position(scene: &Scene, index: usize) -> (isize, isize) {
+    let column_count = scene.game_map.map_info.column_count as isize;
+
+    (index as isize % column_count, index as isize / column_count)
+}
+
+pub fn distance(a: (isize, isize), b: (isize, isize)) -> isize {
+    (a.0 - b.0).abs().max((a.1 - b.1).abs())
+}
+
+// take a step towards a cell, walking in to something hostile attacks it.
+// returns false if there was no way to get any closer
+pub fn go_to(
+    scene: &mut Scene,
+    ai_id: usize,
+    pos: (isize, isize),
+    goal: (isize, isize),
+    chase_maps: &mut MapCache,
+) -> bool {
+    if follow_path(scene, ai_id, pos, goal) {
+        return true;
+    }
+
+    let movement = movement_of(&scene.components, ai_id);
+
+    // a path that goes around everyone in the way, it is kept until the goal
+    // moves too far or something along it changes
+    let occupied = occupied_cells(&scene.components.render);
+
+    let path = scene.path_graph.find_path(
+        &scene.game_map,
+        pos,
+        goal,
+        &movement,
+        &occupied,
+    );
+
+    if let Some(path) = path {
+        // the first step is where the ai is standing now and the last is the
+        // goal, which is left off so the target moving about in it does not
+        // throw the path away
+        let mut steps: VecDeque<(isize, isize)> =
+            path.into_iter().skip(1).collect();
+
+        steps.pop_back();
+
+        scene
+            .components
+            .ai_path
+            .insert(ai_id, AiPath { steps, goal });
+
+        if follow_path(scene, ai_id, pos, goal) {
+            return true;
+        }
+    }
+
+    // there is no way through, the shared map ignores other entitys so at
+    // least it can get closer
+    let game_map = &scene.game_map;
+
+    let steps = chase_maps
+        .entry((movement, goal))
+        .or_insert_with(|| DijkstraMap::new(game_map, movement, &[(goal, 0.0)]))
+        .downhill(pos);
+
+    for step in steps {
+        if move_or_attack(scene, ai_id, step) {
+            return true;
+        }
+    }
+
+    false
+}
+
+// step down hill on the map that leads away from a cell
+pub fn flee_from(
+    scene: &mut Scene,
+    ai_id: usize,
+    pos: (isize, isize),
+    from: (isize, isize),
+    chase_maps: &mut MapCache,
+    flee_maps: &mut MapCache,
+) -> bool {
+    scene.components.ai_path.remove(&ai_id);
+
+    let movement = movement_of(&scene.components, ai_id);
+
+    let home = scene
+        .components
+        .ai
+        .get(&ai_id)
+        .map(|ai| position(scene, ai.home));
+
+    let game_map = &scene.game_map;
+
+    let flee_map = flee_maps.entry((movement, from)).or_insert_with(|| {
+        chase_maps
+            .entry((movement, from))
+            .or_insert_with(|| {
+                DijkstraMap::new(game_map, movement, &[(from, 0.0)])
+            })
+            .flee(game_map)
+    });
+
+    let home_map = home.map(|home| {
+        &*chase_maps.entry((movement, home)).or_insert_with(|| {
+            DijkstraMap::new(game_map, movement, &[(home, 0.0)])
+        })
+    });
+
+    // it runs the way that also takes it home where it can, the home map is
+    // shared but the mix of the two is different for everyone
+    let steps = match home_map {
+        Some(home_map) if home_map.value(pos).is_some() => {
+            flee_map.clone().combine(home_map, HOME_PULL).downhill(pos)
+        }
+        _ => flee_map.downhill(pos),
+    };
+
+    for step in steps {
+        if move_or_attack(scene, ai_id, step) {
+            return true;
+        }
+    }
+
+    false
+}
+
+pub fn wander(scene: &mut Scene, ai_id: usize, pos: (isize, isize)) {
+    let mut rng = game_rng();
+
+    if rng.gen_ratio(1, 2) {
+        let to = (pos.0 + rng.gen_range(-1, 2), pos.1 + rng.gen_range(-1, 2));
+
+        if to != pos {
+            move_or_attack(scene, ai_id, to);
+        }
+    }
+}
+
+// false if it has no leader or the leader is gone
+pub fn follow_leader(
+    scene: &mut Scene,
+    ai_id: usize,
+    pos: (isize, isize),
+    chase_maps: &mut MapCache,
+) -> bool {
+    let leader = scene.components.ai.get(&ai_id).and_then(|ai| ai.leader);
+
+    let leader_index = if let Some(render) =
+        leader.and_then(|leader| scene.components.render.get(&leader))
     {
-        ai_pos.index
+        render.index
+    } else {
+        return false;
+    };
+
+    let leader_pos = position(scene, leader_index);
+
+    if distance(pos, leader_pos) > FOLLOW_DISTANCE {
+        go_to(scene, ai_id, pos, leader_pos, chase_maps);
+    }
+
+    true
+}
+
+// what an ai knows going in to its turn
+#[derive(Debug, Clone, Copy)]
+struct Perception {
+    sees_target: bool,
+    retreating: bool,
+}
+
+// where a target is if it is still around to be fought
+fn target_position(
+    scene: &Scene,
+    target: usize,
+) -> Option<(usize, (isize, isize))> {
+    if !scene.components.health.contains_key(&target) {
+        return None;
+    }
+
+    let render = scene.components.render.get(&target)?;
+
+    Some((target, position(scene, render.index)))
+}
+
+// what each pack can see between all of its members, worked out before any of
+// them move
+fn pack_sightings(scene: &Scene) -> HashMap<usize, usize> {
+    let mut members: Vec<(usize, usize)> = scene
+        .components
+        .pack
+        .iter()
+        .map(|(ent_id, pack)| (*ent_id, pack.id))
+        .collect();
+
+    members.sort_unstable();
+
+    let mut sightings = HashMap::new();
+
+    for (ent_id, pack_id) in members {
+        if sightings.contains_key(&pack_id) {
+            continue;
+        }
+
+        let pos = if let Some(render) = scene.components.render.get(&ent_id) {
+            position(scene, render.index)
+        } else {
+            continue;
+        };
+
+        if let Some((target, _)) = pick_target(scene, ent_id, pos) {
+            sightings.insert(pack_id, target);
+        }
+    }
+
+    sightings
+}
+
+// a pack runs once it is down to half its members or half its health
+fn retreating_packs(scene: &Scene) -> HashSet<usize> {
+    // the members, health and max health of each pack
+    let mut packs: HashMap<usize, (usize, usize, isize, isize)> =
+        HashMap::new();
+
+    for (ent_id, pack) in scene.components.pack.iter() {
+        let health = if let Some(health) = scene.components.health.get(ent_id) {
+            health
+        } else {
+            continue;
+        };
+
+        let entry = packs.entry(pack.id).or_insert((pack.strength, 0, 0, 0));
+
+        entry.1 += 1;
+        entry.2 += health.cur_value;
+        entry.3 += health.max_value;
+    }
+
+    packs
+        .into_iter()
+        .filter(|(_, (strength, members, health, max_health))| {
+            members * 2 <= *strength || health * 2 <= *max_health
+        })
+        .map(|(pack_id, _)| pack_id)
+        .collect()
+}
+
+// keep track of where the target is while it can be seen, once it is gone
+// the memory of it only lasts so long
+fn remember(scene: &mut Scene, ai_id: usize, seen: Option<(isize, isize)>) {
+    let turns = scene.turns;
+
+    let memory = if let Some(memory) = scene.components.memory.get_mut(&ai_id) {
+        memory
     } else {
-        println!("ai has no position");
         return;
     };
 
-    if scene.game_map.render_map[ai_pos_index].lit {
-        let cols = scene.game_map.map_info.column_count;
-        let player_x = (index % cols) as isize;
-        let player_y = (index / cols) as isize;
+    if let Some(seen) = seen {
+        memory.last_seen = Some(seen);
+        memory.seen_at = turns;
+        memory.search_spot = Some(seen);
+    } else if turns > memory.seen_at + memory.forget_after {
+        memory.last_seen = None;
+        memory.search_spot = None;
+    }
+}
 
-        let ai_x = (ai_pos_index % cols) as isize;
-        let ai_y = (ai_pos_index / cols) as isize;
+// somewhere close to where the target was last seen that can be seen from
+// there, where it might have gone
+fn search_spot_near(
+    scene: &Scene,
+    ai_id: usize,
+    pos: (isize, isize),
+    last_seen: (isize, isize),
+) -> Option<(isize, isize)> {
+    let movement = movement_of(&scene.components, ai_id);
 
-        let distance_x = (player_x - ai_x) as isize;
-        let distance_y = (player_y - ai_y) as isize;
+    let spots: Vec<(isize, isize)> = (0..scene.game_map.render_map.len())
+        .filter(|i| can_enter(&scene.game_map, &movement, *i))
+        .map(|i| position(scene, i))
+        .filter(|spot| {
+            *spot != pos
+                && distance(*spot, last_seen) <= SEARCH_RADIUS
+                && can_see(&scene.game_map, last_seen, *spot)
+        })
+        .collect();
 
-        let distance = ((distance_x.pow(2) + distance_y.pow(2)) as f64).sqrt();
+    spots.choose(&mut game_rng()).copied()
+}
+
+// go to where the target was last seen and then look around there, false if
+// there is nothing to look for
+pub fn search(
+    scene: &mut Scene,
+    ai_id: usize,
+    pos: (isize, isize),
+    chase_maps: &mut MapCache,
+) -> bool {
+    let (last_seen, spot) = match scene.components.memory.get(&ai_id) {
+        Some(Memory {
+            last_seen: Some(last_seen),
+            search_spot,
+            ..
+        }) => (*last_seen, *search_spot),
+        _ => return false,
+    };
 
-        let dx = (distance_x as f64 / distance).round() as isize;
-        let dy = (distance_y as f64 / distance).round() as isize;
+    let spot = match spot {
+        Some(spot) if distance(pos, spot) > ARRIVE_DISTANCE => spot,
+        _ => {
+            let spot = if let Some(spot) =
+                search_spot_near(scene, ai_id, pos, last_seen)
+            {
+                spot
+            } else {
+                return false;
+            };
 
-        move_by_system(
-            &mut scene.game_map,
-            &mut scene.components.render,
-            ai_id.to_owned(),
-            (dx, dy),
-        );
+            scene.components.memory.get_mut(&ai_id).unwrap().search_spot =
+                Some(spot);
+
+            spot
+        }
+    };
+
+    // it picks somewhere else next turn if it can not get there
+    if !go_to(scene, ai_id, pos, spot, chase_maps) {
+        scene.components.memory.get_mut(&ai_id).unwrap().search_spot = None;
+    }
+
+    true
+}
+
+// home and a few cells around it that it can see from there
+fn make_patrol(
+    scene: &Scene,
+    ai_id: usize,
+    home: usize,
+) -> Vec<(isize, isize)> {
+    let home_pos = position(scene, home);
+
+    let movement = movement_of(&scene.components, ai_id);
+
+    let mut open: Vec<(isize, isize)> = (0..scene.game_map.render_map.len())
+        .filter(|i| can_enter(&scene.game_map, &movement, *i))
+        .map(|i| position(scene, i))
+        .filter(|pos| {
+            distance(*pos, home_pos) <= PATROL_RADIUS
+                && can_see(&scene.game_map, home_pos, *pos)
+        })
+        .collect();
+
+    open.shuffle(&mut game_rng());
+
+    let mut patrol = vec![home_pos];
+
+    patrol.extend(open.into_iter().take(PATROL_POINTS));
+
+    patrol
+}
+
+fn patrol(
+    scene: &mut Scene,
+    ai_id: usize,
+    pos: (isize, isize),
+    chase_maps: &mut MapCache,
+) {
+    let home = scene.components.ai.get(&ai_id).unwrap().home;
+
+    if scene.components.ai.get(&ai_id).unwrap().patrol.is_empty() {
+        let patrol = make_patrol(scene, ai_id, home);
+
+        scene.components.ai.get_mut(&ai_id).unwrap().patrol = patrol;
+    }
+
+    let ai = scene.components.ai.get_mut(&ai_id).unwrap();
+
+    if distance(pos, ai.patrol[ai.waypoint]) <= ARRIVE_DISTANCE {
+        ai.waypoint = (ai.waypoint + 1) % ai.patrol.len();
+    }
+
+    let goal = ai.patrol[ai.waypoint];
+
+    go_to(scene, ai_id, pos, goal, chase_maps);
+}
+
+// the closest free cell next to the target that no other pack member has
+// already picked this turn
+fn surround_spot(
+    scene: &Scene,
+    ai_id: usize,
+    pos: (isize, isize),
+    target_pos: (isize, isize),
+    claimed: &HashSet<(isize, isize)>,
+) -> Option<(isize, isize)> {
+    let column_count = scene.game_map.map_info.column_count as isize;
+
+    let movement = movement_of(&scene.components, ai_id);
+    let occupied = occupied_cells(&scene.components.render);
+
+    DIRECTIONS
+        .iter()
+        .map(|(dx, dy)| (target_pos.0 + dx, target_pos.1 + dy))
+        .filter(|spot| {
+            let index = (spot.0 + column_count * spot.1) as usize;
+
+            in_bounds(&scene.game_map, *spot)
+                && !claimed.contains(spot)
+                && !occupied.contains(&index)
+                && can_enter(&scene.game_map, &movement, index)
+        })
+        .min_by_key(|spot| (distance(pos, *spot), *spot))
+}
+
+// go for the target, archers keep their distance and shoot when they can
+fn chase(
+    scene: &mut Scene,
+    ai_id: usize,
+    pos: (isize, isize),
+    (target, target_pos): (usize, (isize, isize)),
+    chase_maps: &mut MapCache,
+    flee_maps: &mut MapCache,
+    claimed: &mut HashSet<(isize, isize)>,
+) {
+    let column_count = scene.game_map.map_info.column_count as isize;
+
+    let target_index = (target_pos.0 + column_count * target_pos.1) as usize;
+
+    let archer = scene
+        .components
+        .ai
+        .get(&ai_id)
+        .is_some_and(|ai| ai.ai_type == AiType::Archer);
+
+    let backing_off = archer && distance(pos, target_pos) < KEEP_DISTANCE;
+
+    if archer
+        && !backing_off
+        && clear_shot(scene, ai_id, target)
+        && fire(scene, ai_id, target_index)
+    {
+        return;
+    }
+
+    if backing_off {
+        let moved =
+            flee_from(scene, ai_id, pos, target_pos, chase_maps, flee_maps);
+
+        // a cornered archer shoots anyway
+        if !moved && clear_shot(scene, ai_id, target) {
+            fire(scene, ai_id, target_index);
+        }
+
+        return;
+    }
+
+    // pack members spread out around the target instead of lining up
+    // behind each other
+    let in_pack = scene.components.pack.contains_key(&ai_id);
+
+    if in_pack && distance(pos, target_pos) > 1 {
+        if let Some(spot) =
+            surround_spot(scene, ai_id, pos, target_pos, claimed)
+        {
+            claimed.insert(spot);
+
+            if go_to(scene, ai_id, pos, spot, chase_maps) {
+                return;
+            }
+        }
+    }
+
+    go_to(scene, ai_id, pos, target_pos, chase_maps);
+}
+
+fn triggered(
+    scene: &Scene,
+    ai_id: usize,
+    trigger: Trigger,
+    pos: (isize, isize),
+    perception: Perception,
+) -> bool {
+    let ai = scene.components.ai.get(&ai_id).unwrap();
+
+    let sees_target = perception.sees_target;
+
+    match trigger {
+        Trigger::SeesTarget => sees_target,
+        Trigger::Threatened => sees_target && wants_to_flee(scene, ai_id),
+        Trigger::NoTarget => !sees_target,
+        Trigger::PackRetreat => perception.retreating,
+        Trigger::Arrived => match ai.state {
+            AiState::ReturnHome => {
+                distance(pos, position(scene, ai.home)) <= ARRIVE_DISTANCE
+            }
+            _ => false,
+        },
+        Trigger::Alerted => {
+            !sees_target
+                && scene
+                    .components
+                    .memory
+                    .get(&ai_id)
+                    .is_some_and(|memory| memory.last_seen.is_some())
+        }
+        Trigger::Forgot => scene
+            .components
+            .memory
+            .get(&ai_id)
+            .is_none_or(|memory| memory.last_seen.is_none()),
+        Trigger::After(turns) => ai.state_turns >= turns,
+    }
+}
+
+// follow the graph until no more transitions fire, there is a limit in case
+// the graph goes round in a circle
+fn update_state(
+    scene: &mut Scene,
+    ai_id: usize,
+    pos: (isize, isize),
+    perception: Perception,
+) {
+    for _ in 0..MAX_TRANSITIONS {
+        let ai = scene.components.ai.get(&ai_id).unwrap();
+
+        let state = ai.state;
+
+        let next = ai
+            .graph
+            .transitions
+            .iter()
+            .filter(|transition| transition.from.contains(&state))
+            .find(|transition| {
+                triggered(scene, ai_id, transition.trigger, pos, perception)
+            })
+            .map(|transition| transition.to);
+
+        let next = if let Some(next) = next {
+            next
+        } else {
+            return;
+        };
+
+        let ai = scene.components.ai.get_mut(&ai_id).unwrap();
+
+        ai.state = next;
+        ai.state_turns = 0;
+
+        scene.components.ai_path.remove(&ai_id);
     }
 }
 
-pub fn ai_system(scene: &mut Scene) {
-    let column_count = scene.game_map.map_info.column_count;
-
-    let player_index =
-        scene.components.render.get(&scene.player).unwrap().index;
-
-    let player_x = (player_index % column_count) as isize;
-    let player_y = (player_index / column_count) as isize;
-
-    for ai_id in scene.components.ai.keys() {
-        if let Some(ai_ent_render) = scene.components.render.get(ai_id) {
-            if scene.game_map.render_map[ai_ent_render.index].lit {
-                let ai_x = (ai_ent_render.index % column_count) as isize;
-                let ai_y = (ai_ent_render.index / column_count) as isize;
-
-                let path = match astar(
-                    &mut scene.game_map,
-                    (ai_x, ai_y),
-                    (player_x, player_y),
-                ) {
-                    Ok(path) => path,
-                    Err(err) => {
-                        println!("{}", err);
-                        return;
-                    }
-                };
-
-                if let Some(path) = path {
-                    let first = path.first().unwrap().clone();
-
-                    if first != (player_x, player_y) {
-                        continue;
-                    }
-
-                    let last =
-                        path.iter().rev().skip(1).take(1).next().unwrap();
-
-                    move_to_system(
-                        &mut scene.game_map,
-                        &mut scene.components.render,
-                        *ai_id,
-                        *last,
-                    );
-                } else {
-                    // no path to take
-                }
+// give each of the ais one action, in the order they are given
+pub fn ai_system(scene: &mut Scene, entitys: &mut Entitys, ai_ids: &[usize]) {
+    // one map to each goal is shared by every ai that moves the same way,
+    // the maps are only made when something needs them
+    let mut chase_maps: MapCache = HashMap::new();
+    let mut flee_maps: MapCache = HashMap::new();
+
+    // cells next to a target that a pack member is already heading for
+    let mut claimed: HashSet<(isize, isize)> = HashSet::new();
+
+    let sightings = pack_sightings(scene);
+    let retreating = retreating_packs(scene);
+
+    for ai_id in ai_ids.iter().copied() {
+        if scene.game_over() {
+            break;
+        }
+
+        // it could have been killed by an ai that went before it
+        if !scene.components.ai.contains_key(&ai_id) {
+            continue;
+        }
+
+        let pos =
+            if let Some(ai_ent_render) = scene.components.render.get(&ai_id) {
+                position(scene, ai_ent_render.index)
+            } else {
+                continue;
+            };
+
+        if has_status(&scene.components, ai_id, StatusKind::Paralysis) {
+            continue;
+        }
+
+        if has_status(&scene.components, ai_id, StatusKind::Confusion) {
+            stumble(scene, ai_id);
+
+            continue;
+        }
+
+        // anything a pack member can not see for itself it can still know
+        // about from the rest of the pack
+        let pack_id = scene.components.pack.get(&ai_id).map(|pack| pack.id);
+
+        let target = pick_target(scene, ai_id, pos).or_else(|| {
+            pack_id
+                .and_then(|id| sightings.get(&id))
+                .and_then(|target| target_position(scene, *target))
+        });
+
+        remember(scene, ai_id, target.map(|(_, target_pos)| target_pos));
+
+        let tree = scene
+            .components
+            .ai
+            .get(&ai_id)
+            .and_then(|ai| ai.tree.as_ref())
+            .and_then(|name| scene.behavior_trees.get(name))
+            .cloned();
+
+        if let Some(tree) = tree {
+            let mut context = TreeContext {
+                ai_id,
+                pos,
+                target,
+                chase_maps: &mut chase_maps,
+                flee_maps: &mut flee_maps,
+            };
+
+            run_tree(scene, entitys, &tree, &mut context);
+
+            continue;
+        }
+
+        let perception = Perception {
+            sees_target: target.is_some(),
+            retreating: pack_id.is_some_and(|id| retreating.contains(&id)),
+        };
+
+        update_state(scene, ai_id, pos, perception);
+
+        let ai = scene.components.ai.get(&ai_id).unwrap();
+
+        let (state, home) = (ai.state, ai.home);
+
+        match (state, target) {
+            (AiState::Idle, _) => {}
+            (AiState::Wander, _) => wander(scene, ai_id, pos),
+            (AiState::Patrol, _) => patrol(scene, ai_id, pos, &mut chase_maps),
+            (AiState::Follow, _) => {
+                follow_leader(scene, ai_id, pos, &mut chase_maps);
+            }
+            (AiState::Chase, Some((target, target_pos))) => chase(
+                scene,
+                ai_id,
+                pos,
+                (target, target_pos),
+                &mut chase_maps,
+                &mut flee_maps,
+                &mut claimed,
+            ),
+            (AiState::Flee, Some((_, target_pos))) => {
+                flee_from(
+                    scene,
+                    ai_id,
+                    pos,
+                    target_pos,
+                    &mut chase_maps,
+                    &mut flee_maps,
+                );
             }
+            (AiState::Search, _) => {
+                search(scene, ai_id, pos, &mut chase_maps);
+            }
+            (AiState::ReturnHome, _) => {
+                let home_pos = position(scene, home);
+
+                go_to(scene, ai_id, pos, home_pos, &mut chase_maps);
+            }
+            // chasing and fleeing need something to do it to
+            (AiState::Chase | AiState::Flee, None) => {}
+        }
+
+        if let Some(ai) = scene.components.ai.get_mut(&ai_id) {
+            ai.state_turns += 1;
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{components::Render, game_map::test_map, scenes::SceneBuilder};
+
+    fn put(scene: &mut Scene, ent_id: usize, pos: (isize, isize)) {
+        let index = (pos.0 + 10 * pos.1) as usize;
+
+        scene.components.render.insert(
+            ent_id,
+            Render {
+                size: EntitySize::Medium,
+                index,
+                visible: true,
+                reper_char: 'g',
+            },
+        );
+
+        scene.game_map.notify(MapChange::Occupancy(index));
+    }
+
+    fn position_of(scene: &Scene, ent_id: usize) -> (isize, isize) {
+        position(scene, scene.components.render[&ent_id].index)
+    }
+
+    #[test]
+    fn paths_live_through_small_moves_but_not_new_walls() {
+        let mut scene = SceneBuilder::new()
+            .set_game_map(test_map(&[
+                "##########",
+                "#........#",
+                "#........#",
+                "##########",
+            ]))
+            .set_components(ComponentStore::default())
+            .build();
+
+        let mut chase_maps = MapCache::new();
+
+        put(&mut scene, 1, (1, 1));
+        put(&mut scene, 2, (6, 1));
+
+        scene.process_map_changes();
+
+        assert!(go_to(&mut scene, 1, (1, 1), (6, 1), &mut chase_maps));
+        assert_eq!(position_of(&scene, 1), (2, 1));
+
+        // the goal is not part of the path, so the target stepping out of it
+        // and staying close only moves the goal
+        let old_index = scene.components.render[&2].index;
+
+        scene.game_map.notify(MapChange::Occupancy(old_index));
+
+        put(&mut scene, 2, (7, 2));
+
+        scene.process_map_changes();
+
+        let steps = scene.components.ai_path[&1].steps.clone();
+
+        assert_eq!(scene.components.ai_path[&1].goal, (6, 1));
+
+        assert!(go_to(&mut scene, 1, (2, 1), (7, 2), &mut chase_maps));
+        assert_eq!(Some(&position_of(&scene, 1)), steps.front());
+
+        scene.process_map_changes();
+
+        // still the old path, it was not planned again
+        assert_eq!(scene.components.ai_path[&1].goal, (6, 1));
+
+        // a wall on a step further along throws it away
+        let ahead = scene.components.ai_path[&1].steps[1];
+
+        scene
+            .game_map
+            .set_terrain((ahead.0 + 10 * ahead.1) as usize, '#');
+
+        scene.process_map_changes();
+
+        assert!(!scene.components.ai_path.contains_key(&1));
+    }
+}
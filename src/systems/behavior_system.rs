@@ -0,0 +1,204 @@
+use rand::prelude::*;
+
+use crate::{
+    behavior_tree::{Leaf, Node},
+    components::ItemKind,
+    consumables::{Targeting, CONSUMABLES},
+    entitys::Entitys,
+    los::can_see,
+    rng::game_rng,
+    scenes::Scene,
+};
+
+use super::{
+    ai_system::{
+        distance, flee_from, follow_leader, go_to, position, search, wander,
+        wants_to_flee, MapCache, ARRIVE_DISTANCE,
+    },
+    combat_system::{melee_attack, name_of},
+    item_system::use_item,
+    ranged_system::{clear_shot, fire},
+};
+
+// what the tree is being run for this turn
+pub struct TreeContext<'a> {
+    pub ai_id: usize,
+    pub pos: (isize, isize),
+    pub target: Option<(usize, (isize, isize))>,
+    pub chase_maps: &'a mut MapCache,
+    pub flee_maps: &'a mut MapCache,
+}
+
+// the slot of the first item the ai carries with a name
+fn find_item(scene: &Scene, ai_id: usize, name: &str) -> Option<usize> {
+    let inventory = scene.components.inventory.get(&ai_id)?;
+
+    inventory
+        .items
+        .iter()
+        .position(|item_id| name_of(scene, *item_id) == name)
+}
+
+// items that work on a cell are aimed at the target, so they need one that
+// can be seen and is in range
+fn use_named_item(
+    scene: &mut Scene,
+    entitys: &mut Entitys,
+    context: &TreeContext,
+    name: &str,
+) -> bool {
+    let slot = if let Some(slot) = find_item(scene, context.ai_id, name) {
+        slot
+    } else {
+        return false;
+    };
+
+    let item_id = scene
+        .components
+        .inventory
+        .get(&context.ai_id)
+        .unwrap()
+        .items[slot];
+
+    let targeting = match scene.components.item.get(&item_id) {
+        Some(item) => match item.kind {
+            ItemKind::Consumable(def_index) => CONSUMABLES[def_index].targeting,
+            ItemKind::Equipment => return false,
+        },
+        None => return false,
+    };
+
+    let column_count = scene.game_map.map_info.column_count as isize;
+
+    let target = match (targeting, context.target) {
+        (Targeting::User, _) => None,
+        (Targeting::Cell { range }, Some((_, target_pos))) => {
+            if distance(context.pos, target_pos) > range
+                || !can_see(&scene.game_map, context.pos, target_pos)
+            {
+                return false;
+            }
+
+            Some((target_pos.0 + column_count * target_pos.1) as usize)
+        }
+        (Targeting::Cell { .. }, None) => return false,
+    };
+
+    use_item(scene, entitys, context.ai_id, slot, target)
+}
+
+fn run_leaf(
+    scene: &mut Scene,
+    entitys: &mut Entitys,
+    leaf: &Leaf,
+    context: &mut TreeContext,
+) -> bool {
+    let ai_id = context.ai_id;
+    let pos = context.pos;
+
+    let home = if let Some(ai) = scene.components.ai.get(&ai_id) {
+        position(scene, ai.home)
+    } else {
+        return false;
+    };
+
+    match (leaf, context.target) {
+        (Leaf::SeesTarget, target) => target.is_some(),
+        (Leaf::Hurt, _) => wants_to_flee(scene, ai_id),
+        (Leaf::TargetWithin(range), Some((_, target_pos))) => {
+            distance(pos, target_pos) <= *range
+        }
+        (Leaf::ClearShot, Some((target, _))) => {
+            clear_shot(scene, ai_id, target)
+        }
+        (Leaf::AtHome, _) => distance(pos, home) <= ARRIVE_DISTANCE,
+        (Leaf::Chase, Some((_, target_pos))) => {
+            go_to(scene, ai_id, pos, target_pos, context.chase_maps)
+        }
+        (Leaf::Flee, Some((_, target_pos))) => flee_from(
+            scene,
+            ai_id,
+            pos,
+            target_pos,
+            context.chase_maps,
+            context.flee_maps,
+        ),
+        (Leaf::Attack, Some((target, target_pos))) => {
+            if distance(pos, target_pos) > 1 {
+                return false;
+            }
+
+            melee_attack(scene, ai_id, target);
+
+            true
+        }
+        (Leaf::Shoot, Some((target, target_pos))) => {
+            let column_count = scene.game_map.map_info.column_count as isize;
+
+            let target_index =
+                (target_pos.0 + column_count * target_pos.1) as usize;
+
+            clear_shot(scene, ai_id, target) && fire(scene, ai_id, target_index)
+        }
+        (Leaf::Wander, _) => {
+            wander(scene, ai_id, pos);
+
+            true
+        }
+        (Leaf::GoHome, _) => {
+            distance(pos, home) > ARRIVE_DISTANCE
+                && go_to(scene, ai_id, pos, home, context.chase_maps)
+        }
+        (Leaf::Search, _) => search(scene, ai_id, pos, context.chase_maps),
+        (Leaf::Follow, _) => {
+            follow_leader(scene, ai_id, pos, context.chase_maps)
+        }
+        (Leaf::Idle, _) => true,
+        (Leaf::UseItem(name), _) => {
+            use_named_item(scene, entitys, context, name)
+        }
+        // everything else needs a target
+        (_, None) => false,
+    }
+}
+
+// run a node and say if it succeeded
+pub fn run_tree(
+    scene: &mut Scene,
+    entitys: &mut Entitys,
+    node: &Node,
+    context: &mut TreeContext,
+) -> bool {
+    match node {
+        Node::Sequence(children) => children
+            .iter()
+            .all(|child| run_tree(scene, entitys, child, context)),
+        Node::Selector(children) => children
+            .iter()
+            .any(|child| run_tree(scene, entitys, child, context)),
+        Node::Invert(child) => !run_tree(scene, entitys, child, context),
+        Node::Cooldown { id, turns, child } => {
+            let ready = scene
+                .components
+                .ai
+                .get(&context.ai_id)
+                .and_then(|ai| ai.tree_ready.get(id))
+                .is_none_or(|ready| scene.turns >= *ready);
+
+            if !ready || !run_tree(scene, entitys, child, context) {
+                return false;
+            }
+
+            if let Some(ai) = scene.components.ai.get_mut(&context.ai_id) {
+                ai.tree_ready.insert(*id, scene.turns + turns);
+            }
+
+            true
+        }
+        Node::Chance { one_in, child } => {
+            game_rng().gen_ratio(1, *one_in)
+                && run_tree(scene, entitys, child, context)
+        }
+        Node::Leaf(leaf) => run_leaf(scene, entitys, leaf, context),
+    }
+}
@@ -0,0 +1,214 @@
+use crate::{
+    components::EntitySize,
+    factions::{relation_between, Relation},
+    game_map::MapChange,
+    scenes::Scene,
+    stats::effective_stats,
+};
+
+use super::{
+    item_system::drop_all,
+    move_system::{move_to_system, MoveResult},
+    noise_system::{make_noise, FIGHT_NOISE},
+    progression_system::reward_kill,
+};
+
+pub fn name_of(scene: &Scene, entity_id: usize) -> String {
+    if let Some(name) = scene.components.name.get(&entity_id) {
+        name.value.clone()
+    } else {
+        String::from("something")
+    }
+}
+
+// only things that can be hurt can be attacked, and only by something whose
+// faction is hostile to theirs
+pub fn is_hostile(scene: &Scene, attacker: usize, target: usize) -> bool {
+    if !scene.components.health.contains_key(&target) {
+        return false;
+    }
+
+    relation_between(&scene.components, attacker, target) == Relation::Hostile
+}
+
+// the creature standing in a cell if there is one
+pub fn creature_at(scene: &Scene, index: usize) -> Option<usize> {
+    scene
+        .components
+        .render
+        .iter()
+        .find(|(ent_id, render)| {
+            render.index == index
+                && render.size > EntitySize::Nothing
+                && scene.components.health.contains_key(ent_id)
+        })
+        .map(|(ent_id, _)| *ent_id)
+}
+
+// hurt the target with an attack of some power, its armor takes some of the
+// damage off
+pub fn attack_with(
+    scene: &mut Scene,
+    attacker: usize,
+    target: usize,
+    power: isize,
+    verb: &str,
+) {
+    let armor = effective_stats(&scene.components, target).defense;
+
+    let damage = (power - armor).max(0);
+
+    let attacker_name = name_of(scene, attacker);
+    let target_name = name_of(scene, target);
+
+    // a fight can be heard from wherever the one being hit is
+    if let Some(render) = scene.components.render.get(&target) {
+        let index = render.index;
+
+        make_noise(scene, attacker, index, FIGHT_NOISE);
+    }
+
+    let dead = if let Some(health) = scene.components.health.get_mut(&target) {
+        health.cur_value -= damage;
+
+        health.cur_value <= 0
+    } else {
+        return;
+    };
+
+    if damage > 0 {
+        scene.add_message(format!(
+            "{} {} {} for {}",
+            attacker_name, verb, target_name, damage
+        ));
+    } else {
+        scene.add_message(format!(
+            "{} {} {} but does no damage",
+            attacker_name, verb, target_name
+        ));
+    }
+
+    if dead {
+        kill(scene, target, attacker);
+    }
+}
+
+pub fn melee_attack(scene: &mut Scene, attacker: usize, target: usize) {
+    let power = effective_stats(&scene.components, attacker).attack;
+
+    attack_with(scene, attacker, target, power, "hits");
+}
+
+// the entity stops being a creature and what is left is a corpse on the floor
+// with whatever it carried, the players are left alone so the game over screen can show what happened
+pub fn kill(scene: &mut Scene, target: usize, killer: usize) {
+    let target_name = name_of(scene, target);
+
+    scene.add_message(format!("{} dies", target_name));
+
+    if scene.is_player(target) {
+        let cause = format!("killed by {}", name_of(scene, killer));
+
+        // the others play on in a versus game, so they are left lying there
+        // like anything else
+        if !scene.versus() {
+            scene.death_cause = Some(cause);
+
+            return;
+        }
+
+        let label = scene.player_label(target);
+
+        scene.death_cause = Some(format!("{} was {}", label, cause));
+    }
+
+    if scene.is_player(killer) {
+        scene.kills += 1;
+    }
+
+    reward_kill(scene, killer, target);
+
+    drop_all(scene, target);
+
+    let components = &mut scene.components;
+
+    components.remove_all_but_body(target);
+
+    if let Some(name) = components.name.get_mut(&target) {
+        name.value = format!("{} corpse", name.value);
+    }
+
+    if let Some(render) = components.render.get_mut(&target) {
+        render.reper_char = '%';
+        render.size = EntitySize::Nothing;
+
+        scene.game_map.notify(MapChange::Occupancy(render.index));
+    }
+}
+
+// step to a cell and attack whoever is in it if they are an enemy, returns
+// false if nothing happened
+pub fn move_or_attack(
+    scene: &mut Scene,
+    entity_id: usize,
+    to: (isize, isize),
+) -> bool {
+    match move_to_system(
+        &mut scene.game_map,
+        &mut scene.components,
+        entity_id,
+        to,
+    ) {
+        MoveResult::Moved => true,
+        MoveResult::Bump(target) if is_hostile(scene, entity_id, target) => {
+            melee_attack(scene, entity_id, target);
+
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        components::ComponentStore, entitys::Entitys, game_map::test_map,
+        map_gen::spawner::spawn, scenes::SceneBuilder,
+    };
+
+    #[test]
+    fn the_dead_drop_what_they_carry() {
+        let mut scene = SceneBuilder::new()
+            .set_game_map(test_map(&["....."]))
+            .set_components(ComponentStore::default())
+            .build();
+
+        let mut entitys = Entitys::new();
+
+        let necromancer =
+            spawn(&mut scene.components, &mut entitys, 'N', 2).unwrap();
+
+        let items = scene.components.inventory[&necromancer].items.clone();
+
+        assert!(!items.is_empty());
+
+        kill(&mut scene, necromancer, necromancer);
+
+        for item_id in items {
+            assert_eq!(scene.components.render[&item_id].index, 2);
+            assert!(scene.components.item.contains_key(&item_id));
+        }
+
+        // the corpse is only something to look at now
+        let components = &scene.components;
+
+        assert_eq!(components.render[&necromancer].reper_char, '%');
+        assert!(components.name[&necromancer].value.ends_with("corpse"));
+        assert!(!components.inventory.contains_key(&necromancer));
+        assert!(!components.health.contains_key(&necromancer));
+        assert!(!components.ai.contains_key(&necromancer));
+        assert!(!components.speed.contains_key(&necromancer));
+        assert!(!components.faction.contains_key(&necromancer));
+    }
+}
@@ -0,0 +1,330 @@
+use rand::prelude::*;
+
+use crate::{
+    animation::Projectile,
+    components::{
+        EntitySize, Faction, LightSource, Movement, Name, Render, StatusKind,
+    },
+    consumables::Effect,
+    entitys::Entitys,
+    game_map::MapChange,
+    los::can_see,
+    map_gen::spawner::spawn,
+    movement::{can_enter, in_bounds, movement_of, occupied_cells},
+    rng::game_rng,
+    scenes::Scene,
+};
+
+use super::{
+    combat_system::{attack_with, creature_at, kill, name_of},
+    move_system::DIRECTIONS,
+    noise_system::{make_noise, BLAST_NOISE},
+    ranged_system::projectile_path,
+    status_system::add_status,
+};
+
+fn heal(scene: &mut Scene, index: usize, amount: isize) {
+    let target = if let Some(target) = creature_at(scene, index) {
+        target
+    } else {
+        scene.add_message(String::from("nothing happens"));
+
+        return;
+    };
+
+    if let Some(health) = scene.components.health.get_mut(&target) {
+        health.cur_value = (health.cur_value + amount).min(health.max_value);
+    }
+
+    if target == scene.player {
+        scene.add_message(String::from("you feel better"));
+    } else {
+        let name = name_of(scene, target);
+
+        scene.add_message(format!("{} looks better", name));
+    }
+}
+
+// everything the blast can see from the center gets hurt, walls keep it out
+fn area_damage(
+    scene: &mut Scene,
+    user: usize,
+    index: usize,
+    radius: isize,
+    damage: isize,
+) {
+    let column_count = scene.game_map.map_info.column_count as isize;
+
+    let center = (index as isize % column_count, index as isize / column_count);
+
+    scene.add_message(String::from("there is an explosion"));
+
+    make_noise(scene, user, index, BLAST_NOISE);
+
+    let mut caught: Vec<usize> = scene
+        .components
+        .health
+        .keys()
+        .filter(|ent_id| {
+            let render =
+                if let Some(render) = scene.components.render.get(ent_id) {
+                    render
+                } else {
+                    return false;
+                };
+
+            let pos = (
+                render.index as isize % column_count,
+                render.index as isize / column_count,
+            );
+
+            (pos.0 - center.0).pow(2) + (pos.1 - center.1).pow(2)
+                <= radius.pow(2)
+                && can_see(&scene.game_map, center, pos)
+        })
+        .copied()
+        .collect();
+
+    caught.sort_unstable();
+
+    for target in caught {
+        let name = name_of(scene, target);
+
+        let dead =
+            if let Some(health) = scene.components.health.get_mut(&target) {
+                health.cur_value -= damage;
+
+                health.cur_value <= 0
+            } else {
+                continue;
+            };
+
+        scene.add_message(format!(
+            "{} is caught in the blast for {}",
+            name, damage
+        ));
+
+        if dead {
+            kill(scene, target, user);
+        }
+    }
+}
+
+// anywhere on the map the creature could stand that no one else is in
+fn teleport(scene: &mut Scene, index: usize) {
+    let target = if let Some(target) = creature_at(scene, index) {
+        target
+    } else {
+        scene.add_message(String::from("nothing happens"));
+
+        return;
+    };
+
+    let movement = movement_of(&scene.components, target);
+    let occupied = occupied_cells(&scene.components.render);
+
+    let open: Vec<usize> = (0..scene.game_map.render_map.len())
+        .filter(|i| {
+            !occupied.contains(i) && can_enter(&scene.game_map, &movement, *i)
+        })
+        .collect();
+
+    let new_index = if let Some(new_index) = open.choose(&mut game_rng()) {
+        *new_index
+    } else {
+        scene.add_message(String::from("nothing happens"));
+
+        return;
+    };
+
+    scene.components.render.get_mut(&target).unwrap().index = new_index;
+    scene.components.ai_path.remove(&target);
+
+    scene.game_map.notify(MapChange::Occupancy(index));
+    scene.game_map.notify(MapChange::Occupancy(new_index));
+
+    if target == scene.player {
+        scene.add_message(String::from("the world spins around you"));
+    } else {
+        let name = name_of(scene, target);
+
+        scene.add_message(format!("{} vanishes", name));
+    }
+}
+
+fn status(
+    scene: &mut Scene,
+    index: usize,
+    kind: StatusKind,
+    turns: usize,
+    potency: isize,
+) {
+    if let Some(target) = creature_at(scene, index) {
+        add_status(scene, target, kind, turns, potency);
+    } else {
+        scene.add_message(String::from("nothing happens"));
+    }
+}
+
+fn reveal_map(scene: &mut Scene) {
+    for cell in scene.game_map.render_map.iter_mut() {
+        cell.visited = true;
+    }
+
+    scene.add_message(String::from("the shape of the dungeon comes to you"));
+}
+
+// a small light that stays where it was put
+fn light(
+    scene: &mut Scene,
+    entitys: &mut Entitys,
+    index: usize,
+    radius: isize,
+) {
+    let light_id = entitys.new_id();
+
+    scene.components.name.insert(
+        light_id,
+        Name {
+            value: String::from("glowing mote"),
+        },
+    );
+
+    scene.components.render.insert(
+        light_id,
+        Render {
+            index,
+            reper_char: '°',
+            size: EntitySize::Nothing,
+            visible: true,
+        },
+    );
+
+    scene.components.light_source.insert(
+        light_id,
+        LightSource {
+            radius,
+            color: (200, 220, 255),
+            intensity: 1.0,
+            flicker: 0.0,
+        },
+    );
+
+    scene.add_message(String::from("a glowing mote appears"));
+}
+
+// the bolt goes as far as the target and no further
+fn bolt(scene: &mut Scene, user: usize, index: usize, damage: isize) {
+    let column_count = scene.game_map.map_info.column_count as isize;
+
+    let from = if let Some(render) = scene.components.render.get(&user) {
+        (
+            render.index as isize % column_count,
+            render.index as isize / column_count,
+        )
+    } else {
+        return;
+    };
+
+    let to = (index as isize % column_count, index as isize / column_count);
+
+    let range = (to.0 - from.0).abs().max((to.1 - from.1).abs());
+
+    let (path, hit) = projectile_path(scene, user, from, to, range);
+
+    scene.projectiles.push(Projectile::new(path, '*'));
+
+    if let Some(hit) = hit {
+        attack_with(scene, user, hit, damage, "blasts");
+    } else {
+        scene.add_message(String::from("the bolt hits nothing"));
+    }
+}
+
+// the summon takes the side of whoever called it up
+fn summon(
+    scene: &mut Scene,
+    entitys: &mut Entitys,
+    user: usize,
+    index: usize,
+    map_char: char,
+) {
+    let column_count = scene.game_map.map_info.column_count as isize;
+
+    let x = index as isize % column_count;
+    let y = index as isize / column_count;
+
+    let occupied = occupied_cells(&scene.components.render);
+
+    let open: Vec<usize> = DIRECTIONS
+        .iter()
+        .map(|(dx, dy)| (x + dx, y + dy))
+        .filter(|pos| in_bounds(&scene.game_map, *pos))
+        .map(|(x, y)| (x + column_count * y) as usize)
+        .filter(|i| {
+            !occupied.contains(i)
+                && can_enter(&scene.game_map, &Movement::default(), *i)
+        })
+        .collect();
+
+    let spawn_index = if let Some(spawn_index) = open.choose(&mut game_rng()) {
+        *spawn_index
+    } else {
+        scene.add_message(String::from("nothing happens"));
+
+        return;
+    };
+
+    let summon_id = if let Some(summon_id) =
+        spawn(&mut scene.components, entitys, map_char, spawn_index)
+    {
+        summon_id
+    } else {
+        return;
+    };
+
+    if let Some(faction) = scene.components.faction.get(&user) {
+        let kind = faction.kind;
+
+        scene.components.faction.insert(summon_id, Faction { kind });
+    }
+
+    if let Some(ai) = scene.components.ai.get_mut(&summon_id) {
+        ai.leader = Some(user);
+    }
+
+    scene.game_map.notify(MapChange::Occupancy(spawn_index));
+
+    let name = name_of(scene, summon_id);
+
+    scene.add_message(format!("a {} appears", name));
+}
+
+// do what an effect does to the target cell, effects that work on the user
+// are given the cell the user is standing in
+pub fn apply_effect(
+    scene: &mut Scene,
+    entitys: &mut Entitys,
+    user: usize,
+    effect: Effect,
+    index: usize,
+) {
+    match effect {
+        Effect::Heal(amount) => heal(scene, index, amount),
+        Effect::AreaDamage { radius, damage } => {
+            area_damage(scene, user, index, radius, damage)
+        }
+        Effect::Teleport => teleport(scene, index),
+        Effect::Status {
+            kind,
+            turns,
+            potency,
+        } => status(scene, index, kind, turns, potency),
+        Effect::RevealMap => reveal_map(scene),
+        Effect::Light { radius } => light(scene, entitys, index, radius),
+        Effect::Bolt { damage } => bolt(scene, user, index, damage),
+        Effect::Summon(map_char) => {
+            summon(scene, entitys, user, index, map_char)
+        }
+    }
+}
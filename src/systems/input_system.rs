@@ -1,21 +1,197 @@
 use sdl2::{event::Event, keyboard::Keycode};
 
-use crate::{scenes::Scene, LoopState};
+use crate::{
+    classes::CLASSES,
+    components::{Sneaking, StatusKind},
+    entitys::Entitys,
+    initialize::create_character,
+    scenes::Scene,
+    stats::ranged_of,
+    InventoryAction, LoopState, TargetAction,
+};
 
-use super::move_system::move_by_system;
+use super::{
+    combat_system::{is_hostile, melee_attack},
+    item_system::{drop_item, item_in_slot, pick_up, use_item},
+    move_system::{move_by_system, MoveResult},
+    noise_system::{make_noise, SNEAK_NOISE, WALK_NOISE},
+    progression_system::{
+        apply_level_up, level_up_choices, level_up_waiting, use_ability,
+    },
+    ranged_system::{fire, throw_item},
+    status_system::has_status,
+    target_system::{
+        cycle_target, move_cursor, start_targeting, target_range, valid_target,
+    },
+    turn_system::QUICK_ACTION_COST,
+};
 
+// the keys each player moves with, the first player uses the arrows and the
+// second the number pad. every other key works for whoever's turn it is
+struct MoveKeys {
+    up: Keycode,
+    down: Keycode,
+    left: Keycode,
+    right: Keycode,
+}
+
+const MOVE_KEYS: [MoveKeys; 2] = [
+    MoveKeys {
+        up: Keycode::Up,
+        down: Keycode::Down,
+        left: Keycode::Left,
+        right: Keycode::Right,
+    },
+    MoveKeys {
+        up: Keycode::Kp8,
+        down: Keycode::Kp2,
+        left: Keycode::Kp4,
+        right: Keycode::Kp6,
+    },
+];
+
+// which player a movement key belongs to and the way it moves
+fn move_direction(evt: &Event) -> Option<(usize, (isize, isize))> {
+    let keycode = if let Event::KeyDown {
+        keycode: Some(keycode),
+        ..
+    } = evt
+    {
+        *keycode
+    } else {
+        return None;
+    };
+
+    MOVE_KEYS.iter().enumerate().find_map(|(owner, keys)| {
+        let to_move = if keycode == keys.up {
+            (0, -1)
+        } else if keycode == keys.down {
+            (0, 1)
+        } else if keycode == keys.left {
+            (-1, 0)
+        } else if keycode == keys.right {
+            (1, 0)
+        } else {
+            return None;
+        };
+
+        Some((owner, to_move))
+    })
+}
+
+// letters pick an inventory slot, a is the first one
+fn letter_slot(keycode: Keycode) -> Option<usize> {
+    let code = keycode as i32;
+
+    if code >= Keycode::A as i32 && code <= Keycode::Z as i32 {
+        Some((code - Keycode::A as i32) as usize)
+    } else {
+        None
+    }
+}
+
+// moving in to an enemy attacks it
 fn move_player(scene: &mut Scene, to_move: (isize, isize)) -> LoopState {
-    move_by_system(
+    let player = scene.player;
+
+    let result = move_by_system(
         &mut scene.game_map,
-        &mut scene.components.render,
-        scene.player,
+        &mut scene.components,
+        player,
         to_move,
     );
 
+    match result {
+        MoveResult::Moved => {
+            let index = scene.components.render.get(&player).unwrap().index;
+
+            let loudness = if scene.components.sneaking.contains_key(&player) {
+                SNEAK_NOISE
+            } else {
+                WALK_NOISE
+            };
+
+            make_noise(scene, player, index, loudness);
+        }
+        MoveResult::Bump(target) if is_hostile(scene, player, target) => {
+            melee_attack(scene, player, target);
+        }
+        _ => {}
+    }
+
     LoopState::Run
 }
 
+fn toggle_sneak(scene: &mut Scene) {
+    let player = scene.player;
+
+    if scene.components.sneaking.remove(&player).is_some() {
+        scene.add_message(String::from("you stop sneaking"));
+    } else {
+        scene.components.sneaking.insert(player, Sneaking);
+
+        scene.add_message(String::from("you start sneaking"));
+    }
+}
+
+// the keys that take up the player's energy, in real time these wait until
+// the player is ready and everything else works straight away
+pub fn is_action_key(scene: &Scene, evt: &Event) -> bool {
+    let keycode = if let Event::KeyDown {
+        keycode: Some(keycode),
+        ..
+    } = evt
+    {
+        *keycode
+    } else {
+        return false;
+    };
+
+    if keycode == Keycode::Escape {
+        return false;
+    }
+
+    // any key lets the turn pass while paralysed
+    has_status(&scene.components, scene.player, StatusKind::Paralysis)
+        || move_direction(evt).is_some()
+        || keycode == Keycode::G
+}
+
+// a paralysed player can only let the turns pass until it wears off
+fn handle_paralysed(scene: &mut Scene, evt: &Event) -> LoopState {
+    match evt {
+        Event::Quit { .. }
+        | Event::KeyDown {
+            keycode: Some(Keycode::Escape),
+            ..
+        } => LoopState::Quit,
+        Event::KeyDown { .. } => {
+            scene.add_message(String::from("you can not move"));
+
+            LoopState::Run
+        }
+
+        _ => LoopState::Wait,
+    }
+}
+
 fn handle_wait(scene: &mut Scene, evt: &Event) -> LoopState {
+    if has_status(&scene.components, scene.player, StatusKind::Paralysis) {
+        return handle_paralysed(scene, evt);
+    }
+
+    if let Some((owner, to_move)) = move_direction(evt) {
+        // on a shared keyboard the other player's keys do nothing when it is
+        // not their turn, otherwise either set moves whoever's turn it is
+        if scene.shared_keyboard()
+            && scene.players.get(owner) != Some(&scene.player)
+        {
+            return LoopState::Wait;
+        }
+
+        return move_player(scene, to_move);
+    }
+
     match evt {
         Event::Quit { .. }
         | Event::KeyDown {
@@ -23,46 +199,420 @@ fn handle_wait(scene: &mut Scene, evt: &Event) -> LoopState {
             ..
         } => LoopState::Quit,
         Event::KeyDown {
-            keycode: Some(Keycode::Left),
+            keycode: Some(Keycode::G),
             ..
         } => {
-            move_player(scene, (-1, 0));
+            if pick_up(scene, scene.player) {
+                scene.action_cost = QUICK_ACTION_COST;
 
-            LoopState::Run
+                LoopState::Run
+            } else {
+                LoopState::Wait
+            }
         }
         Event::KeyDown {
-            keycode: Some(Keycode::Right),
+            keycode: Some(Keycode::I),
+            ..
+        } => LoopState::Inventory(InventoryAction::Use),
+        Event::KeyDown {
+            keycode: Some(Keycode::D),
+            ..
+        } => LoopState::Inventory(InventoryAction::Drop),
+        Event::KeyDown {
+            keycode: Some(Keycode::T),
+            ..
+        } => LoopState::Inventory(InventoryAction::Throw),
+        Event::KeyDown {
+            keycode: Some(Keycode::C),
+            ..
+        } => LoopState::CharacterSheet,
+        Event::KeyDown {
+            keycode: Some(Keycode::A),
+            ..
+        } => LoopState::Abilities,
+        // it takes no time to change how you move
+        Event::KeyDown {
+            keycode: Some(Keycode::S),
             ..
         } => {
-            move_player(scene, (1, 0));
+            toggle_sneak(scene);
 
-            LoopState::Run
+            LoopState::Wait
         }
+        Event::KeyDown {
+            keycode: Some(Keycode::F),
+            ..
+        } => {
+            if ranged_of(&scene.components, scene.player).is_some() {
+                start_targeting(scene, TargetAction::Fire)
+            } else {
+                scene.add_message(String::from(
+                    "you have nothing to shoot with",
+                ));
+
+                LoopState::Wait
+            }
+        }
+
+        _ => LoopState::Wait,
+    }
+}
+
+// aimed items go to the cursor starting on the player, the rest are used
+// straight away
+fn start_use(
+    scene: &mut Scene,
+    entitys: &mut Entitys,
+    slot: usize,
+) -> Option<LoopState> {
+    let player = scene.player;
+
+    if target_range(scene, TargetAction::UseItem(slot)).is_some() {
+        return Some(start_targeting(scene, TargetAction::UseItem(slot)));
+    }
 
+    if use_item(scene, entitys, player, slot, None) {
+        Some(LoopState::Run)
+    } else {
+        None
+    }
+}
+
+fn handle_inventory(
+    scene: &mut Scene,
+    entitys: &mut Entitys,
+    evt: &Event,
+    action: InventoryAction,
+) -> LoopState {
+    match evt {
+        Event::Quit { .. } => LoopState::Quit,
+        Event::KeyDown {
+            keycode: Some(Keycode::Escape),
+            ..
+        } => LoopState::Wait,
         Event::KeyDown {
-            keycode: Some(Keycode::Down),
+            keycode: Some(keycode),
             ..
         } => {
-            move_player(scene, (0, 1));
+            let slot = if let Some(slot) = letter_slot(*keycode) {
+                slot
+            } else {
+                return LoopState::Inventory(action);
+            };
 
-            LoopState::Run
+            let player = scene.player;
+
+            let next = match action {
+                InventoryAction::Use => start_use(scene, entitys, slot),
+                InventoryAction::Drop => {
+                    if drop_item(scene, player, slot) {
+                        scene.action_cost = QUICK_ACTION_COST;
+
+                        Some(LoopState::Run)
+                    } else {
+                        None
+                    }
+                }
+                InventoryAction::Throw => item_in_slot(scene, player, slot)
+                    .map(|_| start_targeting(scene, TargetAction::Throw(slot))),
+            };
+
+            next.unwrap_or(LoopState::Inventory(action))
         }
+
+        _ => LoopState::Inventory(action),
+    }
+}
+
+// the arrow keys move the cursor and enter uses whatever is being aimed
+fn handle_targeting(
+    scene: &mut Scene,
+    entitys: &mut Entitys,
+    evt: &Event,
+    action: TargetAction,
+) -> LoopState {
+    // either player's keys move the cursor
+    if let Some((_, adjust)) = move_direction(evt) {
+        move_cursor(scene, adjust);
+
+        return LoopState::Targeting(action);
+    }
+
+    match evt {
+        Event::Quit { .. } => LoopState::Quit,
+        Event::KeyDown {
+            keycode: Some(Keycode::Escape),
+            ..
+        } => LoopState::Wait,
         Event::KeyDown {
-            keycode: Some(Keycode::Up),
+            keycode: Some(Keycode::Tab),
             ..
         } => {
-            move_player(scene, (0, -1));
+            cycle_target(scene, action);
 
-            LoopState::Run
+            LoopState::Targeting(action)
+        }
+        Event::KeyDown {
+            keycode: Some(Keycode::Return),
+            ..
         }
+        | Event::KeyDown {
+            keycode: Some(Keycode::KpEnter),
+            ..
+        } => confirm_target(scene, entitys, action),
 
-        _ => LoopState::Wait,
+        _ => LoopState::Targeting(action),
+    }
+}
+
+fn confirm_target(
+    scene: &mut Scene,
+    entitys: &mut Entitys,
+    action: TargetAction,
+) -> LoopState {
+    let range = if let Some(range) = target_range(scene, action) {
+        range
+    } else {
+        return LoopState::Wait;
+    };
+
+    let player = scene.player;
+    let cursor = scene.cursor;
+
+    if !valid_target(scene, player, cursor, range) {
+        scene.add_message(String::from("you cant aim there"));
+
+        return LoopState::Targeting(action);
+    }
+
+    let done = match action {
+        TargetAction::UseItem(slot) => {
+            use_item(scene, entitys, player, slot, Some(cursor))
+        }
+        TargetAction::Fire => fire(scene, player, cursor),
+        TargetAction::Throw(slot) => {
+            throw_item(scene, entitys, player, slot, cursor)
+        }
+    };
+
+    if done {
+        LoopState::Run
+    } else {
+        LoopState::Wait
+    }
+}
+
+fn handle_character_creation(
+    scene: &mut Scene,
+    entitys: &mut Entitys,
+    evt: &Event,
+) -> LoopState {
+    match evt {
+        Event::Quit { .. }
+        | Event::KeyDown {
+            keycode: Some(Keycode::Escape),
+            ..
+        } => LoopState::Quit,
+        Event::KeyDown {
+            keycode: Some(keycode),
+            ..
+        } => match letter_slot(*keycode) {
+            Some(class_index) if class_index < CLASSES.len() => {
+                create_character(scene, entitys, class_index);
+
+                // in a versus game the next player picks theirs
+                let next = scene.players.iter().copied().find(|player| {
+                    !scene.components.class.contains_key(player)
+                });
+
+                if let Some(next) = next {
+                    scene.player = next;
+
+                    return LoopState::CharacterCreation;
+                }
+
+                scene.player = scene.players[0];
+
+                LoopState::Wait
+            }
+            _ => LoopState::CharacterCreation,
+        },
+
+        _ => LoopState::CharacterCreation,
+    }
+}
+
+// there is no backing out of a level up, one of the choices has to be picked
+fn handle_level_up(scene: &mut Scene, evt: &Event) -> LoopState {
+    match evt {
+        Event::Quit { .. } => LoopState::Quit,
+        Event::KeyDown {
+            keycode: Some(keycode),
+            ..
+        } => {
+            let choices = level_up_choices(scene);
+
+            let choice = match letter_slot(*keycode) {
+                Some(slot) if slot < choices.len() => choices[slot],
+                _ => return LoopState::LevelUp,
+            };
+
+            apply_level_up(scene, choice);
+
+            if level_up_waiting(scene) {
+                LoopState::LevelUp
+            } else {
+                LoopState::Wait
+            }
+        }
+
+        _ => LoopState::LevelUp,
+    }
+}
+
+fn handle_character_sheet(evt: &Event) -> LoopState {
+    match evt {
+        Event::Quit { .. } => LoopState::Quit,
+        Event::KeyDown {
+            keycode: Some(Keycode::Escape),
+            ..
+        }
+        | Event::KeyDown {
+            keycode: Some(Keycode::C),
+            ..
+        } => LoopState::Wait,
+
+        _ => LoopState::CharacterSheet,
     }
 }
 
-pub fn handle_events(scene: &mut Scene, evt: &Event) -> LoopState {
+fn handle_abilities(
+    scene: &mut Scene,
+    entitys: &mut Entitys,
+    evt: &Event,
+) -> LoopState {
+    match evt {
+        Event::Quit { .. } => LoopState::Quit,
+        Event::KeyDown {
+            keycode: Some(Keycode::Escape),
+            ..
+        } => LoopState::Wait,
+        Event::KeyDown {
+            keycode: Some(keycode),
+            ..
+        } => {
+            let slot = if let Some(slot) = letter_slot(*keycode) {
+                slot
+            } else {
+                return LoopState::Abilities;
+            };
+
+            let player = scene.player;
+
+            if use_ability(scene, entitys, player, slot) {
+                LoopState::Run
+            } else {
+                LoopState::Abilities
+            }
+        }
+
+        _ => LoopState::Abilities,
+    }
+}
+
+fn handle_game_over(evt: &Event) -> LoopState {
+    match evt {
+        Event::Quit { .. }
+        | Event::KeyDown {
+            keycode: Some(Keycode::Escape),
+            ..
+        } => LoopState::Quit,
+        Event::KeyDown {
+            keycode: Some(Keycode::R),
+            ..
+        } => LoopState::Restart,
+
+        _ => LoopState::GameOver,
+    }
+}
+
+pub fn handle_events(
+    scene: &mut Scene,
+    entitys: &mut Entitys,
+    evt: &Event,
+) -> LoopState {
     match scene.loop_state {
         LoopState::Wait => handle_wait(scene, evt),
+        LoopState::GameOver => handle_game_over(evt),
+        LoopState::Inventory(action) => {
+            handle_inventory(scene, entitys, evt, action)
+        }
+        LoopState::Targeting(action) => {
+            handle_targeting(scene, entitys, evt, action)
+        }
+        LoopState::CharacterCreation => {
+            handle_character_creation(scene, entitys, evt)
+        }
+        LoopState::LevelUp => handle_level_up(scene, evt),
+        LoopState::CharacterSheet => handle_character_sheet(evt),
+        LoopState::Abilities => handle_abilities(scene, entitys, evt),
         _ => LoopState::Wait,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        components::{ComponentStore, StatusEffect, StatusEffects},
+        game_map::test_map,
+        scenes::SceneBuilder,
+    };
+    use sdl2::keyboard::Mod;
+
+    fn key(keycode: Keycode) -> Event {
+        Event::KeyDown {
+            timestamp: 0,
+            window_id: 0,
+            keycode: Some(keycode),
+            scancode: None,
+            keymod: Mod::NOMOD,
+            repeat: false,
+        }
+    }
+
+    #[test]
+    fn only_actions_wait_for_energy() {
+        let mut scene = SceneBuilder::new()
+            .set_game_map(test_map(&["..."]))
+            .set_components(ComponentStore::default())
+            .build();
+
+        for keycode in [Keycode::Up, Keycode::Kp4, Keycode::G].iter() {
+            assert!(is_action_key(&scene, &key(*keycode)));
+        }
+
+        for keycode in
+            [Keycode::Escape, Keycode::I, Keycode::C, Keycode::A].iter()
+        {
+            assert!(!is_action_key(&scene, &key(*keycode)));
+        }
+
+        assert!(!is_action_key(&scene, &Event::Quit { timestamp: 0 }));
+
+        // anything but escape passes the turn while paralysed
+        scene.components.status_effects.insert(
+            scene.player,
+            StatusEffects {
+                effects: vec![StatusEffect {
+                    kind: StatusKind::Paralysis,
+                    turns: 2,
+                    potency: 0,
+                }],
+            },
+        );
+
+        assert!(is_action_key(&scene, &key(Keycode::I)));
+        assert!(!is_action_key(&scene, &key(Keycode::Escape)));
+    }
+}
@@ -0,0 +1,465 @@
+use std::collections::HashMap;
+
+use crate::{
+    components::{EntitySize, Equipment, ItemKind, Render},
+    consumables::{Targeting, CONSUMABLES},
+    entitys::Entitys,
+    scenes::Scene,
+};
+
+use super::{combat_system::name_of, effect_system::apply_effect};
+
+// the name of an item with how many there are if it is a stack, or how many
+// uses it has left
+pub fn item_label(scene: &Scene, item_id: usize) -> String {
+    let name = name_of(scene, item_id);
+
+    if let Some(charges) = scene.components.charges.get(&item_id) {
+        let plural = if charges.count == 1 { "" } else { "s" };
+
+        return format!("{} ({} charge{})", name, charges.count, plural);
+    }
+
+    match scene.components.stack.get(&item_id) {
+        Some(stack) if stack.count > 1 => {
+            format!("{} (x{})", name, stack.count)
+        }
+        _ => name,
+    }
+}
+
+pub fn is_equipped(scene: &Scene, owner: usize, item_id: usize) -> bool {
+    scene
+        .components
+        .equipment
+        .get(&owner)
+        .is_some_and(|equipment| {
+            equipment.slots.values().any(|i| *i == item_id)
+        })
+}
+
+// take an item off if it is being worn
+pub fn unequip(scene: &mut Scene, owner: usize, item_id: usize) {
+    if let Some(equipment) = scene.components.equipment.get_mut(&owner) {
+        equipment.slots.retain(|_, equipped| *equipped != item_id);
+    }
+}
+
+// put on an item or take it off if it is already on, anything else in the
+// same slot comes off
+fn toggle_equip(scene: &mut Scene, owner: usize, item_id: usize) -> bool {
+    let slot =
+        if let Some(equippable) = scene.components.equippable.get(&item_id) {
+            equippable.slot
+        } else {
+            return false;
+        };
+
+    let name = name_of(scene, item_id);
+
+    if is_equipped(scene, owner, item_id) {
+        unequip(scene, owner, item_id);
+
+        scene.add_message(format!("you take off the {}", name));
+
+        return true;
+    }
+
+    let equipment =
+        scene
+            .components
+            .equipment
+            .entry(owner)
+            .or_insert_with(|| Equipment {
+                slots: HashMap::new(),
+            });
+
+    let old = equipment.slots.insert(slot, item_id);
+
+    if let Some(old) = old {
+        let old_name = name_of(scene, old);
+
+        scene.add_message(format!("you take off the {}", old_name));
+    }
+
+    scene.add_message(format!("you put on the {}", name));
+
+    true
+}
+
+// the items lying on the floor in a cell
+fn items_at(scene: &Scene, index: usize) -> Vec<usize> {
+    let mut items: Vec<usize> = scene
+        .components
+        .item
+        .keys()
+        .filter(|item_id| {
+            scene
+                .components
+                .render
+                .get(item_id)
+                .is_some_and(|render| render.index == index)
+        })
+        .copied()
+        .collect();
+
+    items.sort_unstable();
+
+    items
+}
+
+// put an item in to an inventory, a stack of the same kind is added to if
+// there is room for it. returns false if the inventory is full
+fn add_to_inventory(scene: &mut Scene, owner: usize, item_id: usize) -> bool {
+    let kind = if let Some(item) = scene.components.item.get(&item_id) {
+        item.kind
+    } else {
+        return false;
+    };
+
+    let count = scene
+        .components
+        .stack
+        .get(&item_id)
+        .map_or(1, |stack| stack.count);
+
+    let held = if let Some(inventory) = scene.components.inventory.get(&owner) {
+        inventory.items.clone()
+    } else {
+        return false;
+    };
+
+    for held_id in held.iter() {
+        let same_kind = scene
+            .components
+            .item
+            .get(held_id)
+            .is_some_and(|item| item.kind == kind);
+
+        if !same_kind {
+            continue;
+        }
+
+        if let Some(stack) = scene.components.stack.get_mut(held_id) {
+            if stack.count + count <= stack.max {
+                stack.count += count;
+
+                // the picked up item is now part of the stack
+                scene.components.remove_entity(item_id);
+
+                return true;
+            }
+        }
+    }
+
+    let inventory = scene.components.inventory.get_mut(&owner).unwrap();
+
+    if inventory.items.len() >= inventory.capacity {
+        return false;
+    }
+
+    inventory.items.push(item_id);
+
+    scene.components.render.remove(&item_id);
+
+    true
+}
+
+pub fn pick_up(scene: &mut Scene, owner: usize) -> bool {
+    let index = if let Some(render) = scene.components.render.get(&owner) {
+        render.index
+    } else {
+        return false;
+    };
+
+    let items = items_at(scene, index);
+
+    if items.is_empty() {
+        scene.add_message(String::from("there is nothing here"));
+
+        return false;
+    }
+
+    let mut picked_up = false;
+
+    for item_id in items {
+        let label = item_label(scene, item_id);
+
+        if !add_to_inventory(scene, owner, item_id) {
+            scene.add_message(String::from("your pack is full"));
+
+            break;
+        }
+
+        scene.add_message(format!("you pick up {}", label));
+
+        picked_up = true;
+    }
+
+    picked_up
+}
+
+pub fn item_in_slot(scene: &Scene, owner: usize, slot: usize) -> Option<usize> {
+    scene
+        .components
+        .inventory
+        .get(&owner)
+        .and_then(|inventory| inventory.items.get(slot))
+        .copied()
+}
+
+// the whole stack goes on to the floor where the owner is standing
+pub fn drop_item(scene: &mut Scene, owner: usize, slot: usize) -> bool {
+    let item_id = if let Some(item_id) = item_in_slot(scene, owner, slot) {
+        item_id
+    } else {
+        return false;
+    };
+
+    let index = if let Some(render) = scene.components.render.get(&owner) {
+        render.index
+    } else {
+        return false;
+    };
+
+    let glyph = scene.components.item.get(&item_id).map_or('?', |i| i.glyph);
+
+    unequip(scene, owner, item_id);
+
+    scene
+        .components
+        .inventory
+        .get_mut(&owner)
+        .unwrap()
+        .items
+        .remove(slot);
+
+    scene.components.render.insert(
+        item_id,
+        Render {
+            index,
+            reper_char: glyph,
+            size: EntitySize::Nothing,
+            visible: true,
+        },
+    );
+
+    let label = item_label(scene, item_id);
+
+    scene.add_message(format!("you drop {}", label));
+
+    true
+}
+
+// everything an entity carries falls to the floor where it is, for when it
+// dies
+pub fn drop_all(scene: &mut Scene, owner: usize) {
+    let index = if let Some(render) = scene.components.render.get(&owner) {
+        render.index
+    } else {
+        return;
+    };
+
+    let items =
+        if let Some(inventory) = scene.components.inventory.get_mut(&owner) {
+            std::mem::take(&mut inventory.items)
+        } else {
+            return;
+        };
+
+    scene.components.equipment.remove(&owner);
+
+    for item_id in items {
+        let glyph =
+            scene.components.item.get(&item_id).map_or('?', |i| i.glyph);
+
+        scene.components.render.insert(
+            item_id,
+            Render {
+                index,
+                reper_char: glyph,
+                size: EntitySize::Nothing,
+                visible: true,
+            },
+        );
+    }
+}
+
+// use up a charge, or one item from a slot
+fn consume(scene: &mut Scene, owner: usize, slot: usize, item_id: usize) {
+    if let Some(charges) = scene.components.charges.get_mut(&item_id) {
+        if charges.count > 1 {
+            charges.count -= 1;
+
+            return;
+        }
+    }
+
+    if let Some(stack) = scene.components.stack.get_mut(&item_id) {
+        if stack.count > 1 {
+            stack.count -= 1;
+
+            return;
+        }
+    }
+
+    if let Some(inventory) = scene.components.inventory.get_mut(&owner) {
+        inventory.items.remove(slot);
+    }
+
+    scene.components.remove_entity(item_id);
+}
+
+// use an item, targeted items are given the cell that was picked and the rest
+// work on the cell the owner is standing in
+pub fn use_item(
+    scene: &mut Scene,
+    entitys: &mut Entitys,
+    owner: usize,
+    slot: usize,
+    target: Option<usize>,
+) -> bool {
+    let item_id = if let Some(item_id) = item_in_slot(scene, owner, slot) {
+        item_id
+    } else {
+        return false;
+    };
+
+    let kind = if let Some(item) = scene.components.item.get(&item_id) {
+        item.kind
+    } else {
+        return false;
+    };
+
+    let def_index = match kind {
+        ItemKind::Consumable(def_index) => def_index,
+        // equipment is not used up
+        ItemKind::Equipment => return toggle_equip(scene, owner, item_id),
+    };
+
+    let def = &CONSUMABLES[def_index];
+
+    let index = match (def.targeting, target) {
+        (Targeting::User, _) => {
+            if let Some(render) = scene.components.render.get(&owner) {
+                render.index
+            } else {
+                return false;
+            }
+        }
+        (Targeting::Cell { .. }, Some(target)) => target,
+        (Targeting::Cell { .. }, None) => return false,
+    };
+
+    if owner == scene.player {
+        scene.add_message(format!("you use the {}", def.name));
+    } else {
+        let name = name_of(scene, owner);
+
+        scene.add_message(format!("{} uses the {}", name, def.name));
+    }
+
+    apply_effect(scene, entitys, owner, def.effect, index);
+
+    consume(scene, owner, slot, item_id);
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        components::{ComponentStore, Inventory},
+        game_map::test_map,
+        map_gen::spawner::spawn,
+        scenes::SceneBuilder,
+    };
+
+    #[test]
+    fn wands_keep_their_charges_to_themselves() {
+        let mut scene = SceneBuilder::new()
+            .set_game_map(test_map(&["..."]))
+            .set_components(ComponentStore::default())
+            .build();
+
+        let mut entitys = Entitys::new();
+
+        let owner = entitys.new_id();
+
+        scene.components.inventory.insert(
+            owner,
+            Inventory {
+                items: Vec::new(),
+                capacity: 5,
+            },
+        );
+
+        let first = spawn(&mut scene.components, &mut entitys, '/', 0).unwrap();
+        let second =
+            spawn(&mut scene.components, &mut entitys, '/', 0).unwrap();
+
+        assert!(!scene.components.stack.contains_key(&first));
+        assert!(item_label(&scene, first).ends_with("(3 charges)"));
+
+        // two wands never end up in one slot, even of the same kind
+        scene.components.item.get_mut(&second).unwrap().kind =
+            scene.components.item[&first].kind;
+
+        assert!(add_to_inventory(&mut scene, owner, first));
+        assert!(add_to_inventory(&mut scene, owner, second));
+        assert_eq!(
+            scene.components.inventory[&owner].items,
+            vec![first, second]
+        );
+
+        // each use takes a charge and the last one uses it up
+        consume(&mut scene, owner, 0, first);
+        consume(&mut scene, owner, 0, first);
+
+        assert!(item_label(&scene, first).ends_with("(1 charge)"));
+
+        consume(&mut scene, owner, 0, first);
+
+        assert!(!scene.components.item.contains_key(&first));
+        assert_eq!(scene.components.inventory[&owner].items, vec![second]);
+    }
+
+    #[test]
+    fn potions_still_stack() {
+        let mut scene = SceneBuilder::new()
+            .set_game_map(test_map(&["..."]))
+            .set_components(ComponentStore::default())
+            .build();
+
+        let mut entitys = Entitys::new();
+
+        let owner = entitys.new_id();
+
+        scene.components.inventory.insert(
+            owner,
+            Inventory {
+                items: Vec::new(),
+                capacity: 5,
+            },
+        );
+
+        let potions: Vec<usize> = (0..2)
+            .map(|_| {
+                spawn(&mut scene.components, &mut entitys, '!', 0).unwrap()
+            })
+            .collect();
+
+        let kind = scene.components.item[&potions[0]].kind;
+
+        scene.components.item.get_mut(&potions[1]).unwrap().kind = kind;
+
+        for potion in potions.iter() {
+            assert!(add_to_inventory(&mut scene, owner, *potion));
+        }
+
+        assert_eq!(scene.components.inventory[&owner].items, vec![potions[0]]);
+        assert!(item_label(&scene, potions[0]).ends_with("(x2)"));
+        assert!(!scene.components.charges.contains_key(&potions[0]));
+    }
+}
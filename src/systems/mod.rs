@@ -1,3 +1,14 @@
 pub mod ai_system;
+pub mod behavior_system;
+pub mod combat_system;
+pub mod effect_system;
 pub mod input_system;
+pub mod item_system;
 pub mod move_system;
+pub mod net_system;
+pub mod noise_system;
+pub mod progression_system;
+pub mod ranged_system;
+pub mod status_system;
+pub mod target_system;
+pub mod turn_system;
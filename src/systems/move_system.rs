@@ -1,55 +1,102 @@
-use std::collections::HashMap;
+use rand::prelude::*;
 
 use crate::{
-    components::{EntitySize, Render},
-    game_map::GameMap,
+    components::{ComponentStore, EntitySize, StatusKind},
+    game_map::{GameMap, MapChange},
+    movement::{movement_of, step_cost},
+    rng::game_rng,
 };
 
+use super::status_system::has_status;
+
+pub const DIRECTIONS: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+#[derive(Debug, PartialEq)]
+pub enum MoveResult {
+    Moved,
+    Blocked,
+    // someone is standing where we wanted to go
+    Bump(usize),
+}
+
 pub fn move_to_system(
     game_map: &mut GameMap,
-    render: &mut HashMap<usize, Render>,
+    components: &mut ComponentStore,
     entity_id: usize,
     to: (isize, isize),
-) {
+) -> MoveResult {
     let column_count = game_map.map_info.column_count as isize;
     let row_count = game_map.map_info.row_count as isize;
 
     if to.0 < 0 || to.1 < 0 || to.0 >= column_count || to.1 >= row_count {
-        println!("out of board");
-        return;
+        return MoveResult::Blocked;
     }
 
-    let new_i = (to.0 + (column_count * to.1)) as usize;
+    let old_i = if let Some(ent) = components.render.get(&entity_id) {
+        ent.index
+    } else {
+        return MoveResult::Blocked;
+    };
 
-    if game_map.render_map[new_i].terrain_size > EntitySize::Medium {
-        return;
+    let from = (old_i as isize % column_count, old_i as isize / column_count);
+
+    let movement = movement_of(components, entity_id);
+
+    if step_cost(game_map, &movement, from, to).is_none() {
+        return MoveResult::Blocked;
     }
 
-    for ents in render.values() {
-        if ents.index == new_i {
-            return;
+    let new_i = (to.0 + (column_count * to.1)) as usize;
+
+    // things like corpses dont take up any room
+    for (ent_id, ents) in components.render.iter() {
+        if ents.index == new_i && ents.size > EntitySize::Nothing {
+            return MoveResult::Bump(*ent_id);
         }
     }
 
     // we know it exists so unwrap should be fine
-    render.get_mut(&entity_id).unwrap().index = new_i;
+    components.render.get_mut(&entity_id).unwrap().index = new_i;
+
+    game_map.notify(MapChange::Occupancy(old_i));
+    game_map.notify(MapChange::Occupancy(new_i));
+
+    MoveResult::Moved
 }
 
 // we cant borrow the scene as mut in the way we want so we borrow the parts we
 // need
 pub fn move_by_system(
     game_map: &mut GameMap,
-    render: &mut HashMap<usize, Render>,
+    components: &mut ComponentStore,
     entity_id: usize,
     adjust: (isize, isize),
-) {
+) -> MoveResult {
     let column_count = game_map.map_info.column_count;
     let row_count = game_map.map_info.row_count;
 
-    let old_i = if let Some(ent) = render.get(&entity_id) {
+    let old_i = if let Some(ent) = components.render.get(&entity_id) {
         ent.index
     } else {
-        return;
+        return MoveResult::Blocked;
+    };
+
+    // a confused entity has no say in which way it goes
+    let adjust = if has_status(components, entity_id, StatusKind::Confusion) {
+        let mut rng = game_rng();
+
+        *DIRECTIONS.choose(&mut rng).unwrap()
+    } else {
+        adjust
     };
 
     let old_x = old_i % column_count;
@@ -59,30 +106,39 @@ pub fn move_by_system(
     let n_y = old_y as isize + adjust.1;
 
     if n_x < 0 || n_y < 0 {
-        println!("out of board");
-        return;
+        return MoveResult::Blocked;
     }
 
     let n_x = n_x as usize;
     let n_y = n_y as usize;
 
     if n_x >= column_count || n_y >= row_count {
-        println!("out of board");
-        return;
+        return MoveResult::Blocked;
     }
 
-    let new_i = n_x + (column_count * n_y);
+    let movement = movement_of(components, entity_id);
 
-    if game_map.render_map[new_i].terrain_size > EntitySize::Small {
-        return;
+    let from = (old_x as isize, old_y as isize);
+    let to = (n_x as isize, n_y as isize);
+
+    if step_cost(game_map, &movement, from, to).is_none() {
+        return MoveResult::Blocked;
     }
 
-    for ents in render.values() {
-        if ents.index == new_i {
-            return;
+    let new_i = n_x + (column_count * n_y);
+
+    // things like corpses dont take up any room
+    for (ent_id, ents) in components.render.iter() {
+        if ents.index == new_i && ents.size > EntitySize::Nothing {
+            return MoveResult::Bump(*ent_id);
         }
     }
 
     // we know it exists so unwrap should be fine
-    render.get_mut(&entity_id).unwrap().index = new_i;
+    components.render.get_mut(&entity_id).unwrap().index = new_i;
+
+    game_map.notify(MapChange::Occupancy(old_i));
+    game_map.notify(MapChange::Occupancy(new_i));
+
+    MoveResult::Moved
 }
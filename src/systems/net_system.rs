@@ -0,0 +1,201 @@
+use std::error::Error;
+
+use sdl2::{
+    event::Event,
+    keyboard::{Keycode, Mod},
+};
+
+use crate::{
+    entitys::Entitys, net::Lockstep, net::Message, scenes::Scene,
+    state_hash::state_hash, LoopState,
+};
+
+use super::input_system::handle_events;
+
+// only the player whose turn it is gets to press keys
+pub fn local_turn(scene: &Scene, net: &Lockstep) -> bool {
+    scene.players.get(net.local) == Some(&scene.player)
+}
+
+// every key the local player used is played on the other machines too
+pub fn send_key(net: &mut Lockstep, evt: &Event) -> Result<(), Box<dyn Error>> {
+    if let Event::KeyDown {
+        keycode: Some(keycode),
+        ..
+    } = evt
+    {
+        let player = net.local;
+
+        net.send(Message::Key {
+            player,
+            keycode: *keycode as i32,
+        })?;
+    }
+
+    Ok(())
+}
+
+fn key_event(keycode: Keycode) -> Event {
+    Event::KeyDown {
+        timestamp: 0,
+        window_id: 0,
+        keycode: Some(keycode),
+        scancode: None,
+        keymod: Mod::NOMOD,
+        repeat: false,
+    }
+}
+
+// after an action every machine hashes its game, the one who took it sends
+// theirs for the others to check against
+pub fn record_action(
+    scene: &Scene,
+    net: &mut Lockstep,
+    acting: usize,
+) -> Result<(), Box<dyn Error>> {
+    net.actions += 1;
+
+    let hash = state_hash(&scene.components, &scene.game_map);
+
+    net.record_hash(hash);
+
+    if scene.players.get(net.local) == Some(&acting) {
+        let player = net.local;
+        let action = net.actions;
+
+        net.send(Message::Hash {
+            player,
+            action,
+            hash,
+        })?;
+    }
+
+    Ok(())
+}
+
+// play what the other players did, it stops once an action has been taken so
+// the turn is run before anything that came after it
+pub fn net_system(
+    scene: &mut Scene,
+    entitys: &mut Entitys,
+    net: &mut Lockstep,
+) -> Result<(), Box<dyn Error>> {
+    while scene.loop_state != LoopState::Run
+        && scene.loop_state != LoopState::Restart
+    {
+        let message = if let Some(message) = net.next_message()? {
+            message
+        } else {
+            return Ok(());
+        };
+
+        match message {
+            Message::Key { keycode, .. } => {
+                let keycode = if let Some(keycode) = Keycode::from_i32(keycode)
+                {
+                    keycode
+                } else {
+                    continue;
+                };
+
+                // quitting is never sent, but a key that would quit here
+                // should not end the game for everyone
+                match handle_events(scene, entitys, &key_event(keycode)) {
+                    LoopState::Quit => {}
+                    loop_state => scene.loop_state = loop_state,
+                }
+            }
+            Message::Hash {
+                player,
+                action,
+                hash,
+            } => {
+                if !net.in_step(action, hash) {
+                    println!(
+                        "out of step with player {} after action {}",
+                        player + 1,
+                        action
+                    );
+
+                    scene.add_message(format!(
+                        "this game has drifted apart from player {}'s",
+                        player + 1
+                    ));
+                }
+            }
+            Message::Start { .. } => {}
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        components::ComponentStore, game_map::test_map, net::loopback,
+        scenes::SceneBuilder,
+    };
+
+    fn scene() -> Scene {
+        let mut scene = SceneBuilder::new()
+            .set_game_map(test_map(&["#####", "#...#", "#####"]))
+            .set_components(ComponentStore::default())
+            .build();
+
+        scene.players = vec![10, 11];
+
+        scene
+    }
+
+    // run what came in until the first thing it has to say
+    fn first_message(
+        scene: &mut Scene,
+        entitys: &mut Entitys,
+        net: &mut Lockstep,
+    ) -> String {
+        for _ in 0..500 {
+            net_system(scene, entitys, net).unwrap();
+
+            if let Some(message) = scene.messages.first() {
+                return message.clone();
+            }
+
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        panic!("nothing was said");
+    }
+
+    #[test]
+    fn a_changed_game_is_noticed() {
+        let mut nets = loopback(2);
+        let mut client = nets.pop().unwrap();
+        let mut host = nets.pop().unwrap();
+
+        let mut entitys = Entitys::new();
+
+        let mut host_scene = scene();
+        let mut client_scene = scene();
+
+        // the first action leaves both games the same, the second is done
+        // differently on each
+        record_action(&host_scene, &mut host, 10).unwrap();
+        record_action(&client_scene, &mut client, 10).unwrap();
+
+        host_scene.game_map.set_terrain(7, '#');
+        client_scene.game_map.set_terrain(6, '#');
+
+        record_action(&host_scene, &mut host, 10).unwrap();
+        record_action(&client_scene, &mut client, 10).unwrap();
+
+        // both hashes come in after the client has moved on, the first one
+        // still matches
+        assert_eq!(
+            first_message(&mut client_scene, &mut entitys, &mut client),
+            "this game has drifted apart from player 1's"
+        );
+        assert_eq!(client_scene.messages.len(), 1);
+    }
+}
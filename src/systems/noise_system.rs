@@ -0,0 +1,94 @@
+use crate::{
+    components::{Movement, MovementMode},
+    dijkstra_map::DijkstraMap,
+    scenes::Scene,
+};
+
+use super::combat_system::is_hostile;
+
+// how far away each kind of noise can be heard over open floor
+pub const WALK_NOISE: f64 = 4.0;
+pub const SNEAK_NOISE: f64 = 1.0;
+pub const FIGHT_NOISE: f64 = 8.0;
+pub const SHOT_NOISE: f64 = 5.0;
+pub const BLAST_NOISE: f64 = 12.0;
+
+// sound spreads like something that can pass through walls but finds it
+// hard going
+const SOUND: Movement = Movement {
+    mode: MovementMode::Phase,
+    cut_corners: true,
+    occupied_cost: None,
+};
+
+// a sound made this turn, the source is who made it
+pub struct Noise {
+    pub source: usize,
+    pub index: usize,
+    pub loudness: f64,
+}
+
+pub fn make_noise(
+    scene: &mut Scene,
+    source: usize,
+    index: usize,
+    loudness: f64,
+) {
+    scene.noises.push(Noise {
+        source,
+        index,
+        loudness,
+    });
+}
+
+// anything that hears a noise made by something it is hostile to remembers
+// where it came from as if it had seen it there
+pub fn noise_system(scene: &mut Scene) {
+    let noises = std::mem::take(&mut scene.noises);
+
+    let column_count = scene.game_map.map_info.column_count as isize;
+
+    let mut listeners: Vec<usize> =
+        scene.components.memory.keys().copied().collect();
+
+    listeners.sort_unstable();
+
+    for noise in noises.iter() {
+        let pos = (
+            noise.index as isize % column_count,
+            noise.index as isize / column_count,
+        );
+
+        let spread = DijkstraMap::limited(
+            &scene.game_map,
+            SOUND,
+            &[(pos, 0.0)],
+            noise.loudness,
+        );
+
+        for listener in listeners.iter() {
+            let index =
+                if let Some(render) = scene.components.render.get(listener) {
+                    render.index as isize
+                } else {
+                    continue;
+                };
+
+            let heard = spread
+                .value((index % column_count, index / column_count))
+                .is_some();
+
+            if !heard || !is_hostile(scene, *listener, noise.source) {
+                continue;
+            }
+
+            let turns = scene.turns;
+
+            if let Some(memory) = scene.components.memory.get_mut(listener) {
+                memory.last_seen = Some(pos);
+                memory.seen_at = turns;
+                memory.search_spot = Some(pos);
+            }
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use crate::{
+    classes::{ability_def, Ability, ABILITIES},
+    components::{Attack, Defense},
+    entitys::Entitys,
+    scenes::Scene,
+};
+
+use super::effect_system::apply_effect;
+
+// how much health picking health on a level up gives
+const LEVEL_HEALTH: isize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LevelUpChoice {
+    Health,
+    Attack,
+    Defense,
+    Learn(Ability),
+}
+
+// the xp needed to get from a level to the next one
+pub fn xp_to_next(level: usize) -> usize {
+    level * 15
+}
+
+pub fn gain_xp(scene: &mut Scene, entity_id: usize, amount: usize) {
+    let mut reached = vec![];
+
+    if let Some(experience) = scene.components.experience.get_mut(&entity_id) {
+        experience.xp += amount;
+
+        while experience.xp >= xp_to_next(experience.level) {
+            experience.xp -= xp_to_next(experience.level);
+            experience.level += 1;
+            experience.pending += 1;
+
+            reached.push(experience.level);
+        }
+    } else {
+        return;
+    }
+
+    for level in reached {
+        scene.add_message(format!("you reach level {}", level));
+    }
+}
+
+// the killer gets whatever the target was worth
+pub fn reward_kill(scene: &mut Scene, killer: usize, target: usize) {
+    let value = if let Some(reward) = scene.components.xp_reward.get(&target) {
+        reward.value
+    } else {
+        return;
+    };
+
+    gain_xp(scene, killer, value);
+}
+
+pub fn level_up_waiting(scene: &Scene) -> bool {
+    scene
+        .components
+        .experience
+        .get(&scene.player)
+        .is_some_and(|experience| experience.pending > 0)
+}
+
+// the stats can always go up, the next ability the player does not know yet
+// is offered when there is one
+pub fn level_up_choices(scene: &Scene) -> Vec<LevelUpChoice> {
+    let mut choices = vec![
+        LevelUpChoice::Health,
+        LevelUpChoice::Attack,
+        LevelUpChoice::Defense,
+    ];
+
+    let known = scene
+        .components
+        .abilities
+        .get(&scene.player)
+        .map_or(vec![], |abilities| abilities.known.clone());
+
+    let new = ABILITIES.iter().find(|def| !known.contains(&def.ability));
+
+    if let Some(def) = new {
+        choices.push(LevelUpChoice::Learn(def.ability));
+    }
+
+    choices
+}
+
+pub fn choice_label(choice: LevelUpChoice) -> String {
+    match choice {
+        LevelUpChoice::Health => format!("{} more health", LEVEL_HEALTH),
+        LevelUpChoice::Attack => String::from("1 more attack"),
+        LevelUpChoice::Defense => String::from("1 more defense"),
+        LevelUpChoice::Learn(ability) => {
+            format!("learn {}", ability_def(ability).name)
+        }
+    }
+}
+
+pub fn apply_level_up(scene: &mut Scene, choice: LevelUpChoice) {
+    let player = scene.player;
+    let components = &mut scene.components;
+
+    match choice {
+        LevelUpChoice::Health => {
+            if let Some(health) = components.health.get_mut(&player) {
+                health.max_value += LEVEL_HEALTH;
+                health.cur_value += LEVEL_HEALTH;
+            }
+        }
+        LevelUpChoice::Attack => {
+            components
+                .attack
+                .entry(player)
+                .or_insert(Attack { power: 0 })
+                .power += 1;
+        }
+        LevelUpChoice::Defense => {
+            components
+                .defense
+                .entry(player)
+                .or_insert(Defense { armor: 0 })
+                .armor += 1;
+        }
+        LevelUpChoice::Learn(ability) => {
+            if let Some(abilities) = components.abilities.get_mut(&player) {
+                abilities.known.push(ability);
+            }
+        }
+    }
+
+    if let Some(experience) = components.experience.get_mut(&player) {
+        experience.pending = experience.pending.saturating_sub(1);
+    }
+
+    scene.add_message(format!("you gain {}", choice_label(choice)));
+}
+
+// use a known ability if it is ready, it works on the cell the user is in
+pub fn use_ability(
+    scene: &mut Scene,
+    entitys: &mut Entitys,
+    entity_id: usize,
+    slot: usize,
+) -> bool {
+    let ability = match scene.components.abilities.get(&entity_id) {
+        Some(abilities) => match abilities.known.get(slot) {
+            Some(ability) => *ability,
+            None => return false,
+        },
+        None => return false,
+    };
+
+    let def = ability_def(ability);
+
+    let ready = scene
+        .components
+        .abilities
+        .get(&entity_id)
+        .is_some_and(|abilities| !abilities.cooldowns.contains_key(&ability));
+
+    if !ready {
+        scene.add_message(format!("{} is not ready yet", def.name));
+
+        return false;
+    }
+
+    let index = if let Some(render) = scene.components.render.get(&entity_id) {
+        render.index
+    } else {
+        return false;
+    };
+
+    scene.add_message(format!("you use {}", def.name));
+
+    apply_effect(scene, entitys, entity_id, def.effect, index);
+
+    if let Some(abilities) = scene.components.abilities.get_mut(&entity_id) {
+        abilities.cooldowns.insert(ability, def.cooldown);
+    }
+
+    true
+}
+
+// once a turn every ability that was used gets a turn closer to being ready
+pub fn tick_cooldowns(scene: &mut Scene) {
+    for abilities in scene.components.abilities.values_mut() {
+        abilities.cooldowns.retain(|_, turns| {
+            *turns = turns.saturating_sub(1);
+
+            *turns > 0
+        });
+    }
+}
@@ -0,0 +1,252 @@
+use crate::{
+    animation::Projectile,
+    components::{EntitySize, Item, Name, Render, Stack},
+    entitys::Entitys,
+    los::{blocks_sight, line_of_sight},
+    scenes::Scene,
+    stats::ranged_of,
+};
+
+use super::{
+    combat_system::{attack_with, creature_at, name_of},
+    item_system::{item_in_slot, unequip},
+    noise_system::{make_noise, SHOT_NOISE},
+};
+
+// how far anything can be thrown
+pub const THROW_RANGE: isize = 6;
+
+// a thrown thing that is not made for it still hurts a little
+const THROW_POWER: isize = 1;
+
+fn position(scene: &Scene, entity_id: usize) -> Option<(isize, isize)> {
+    let column_count = scene.game_map.map_info.column_count as isize;
+
+    let index = scene.components.render.get(&entity_id)?.index as isize;
+
+    Some((index % column_count, index / column_count))
+}
+
+// an arrow points the way it is flying
+fn arrow_glyph(from: (isize, isize), to: (isize, isize)) -> char {
+    let dx = (to.0 - from.0).signum();
+    let dy = (to.1 - from.1).signum();
+
+    match (dx, dy) {
+        (0, _) => '|',
+        (_, 0) => '-',
+        (1, 1) | (-1, -1) => '\\',
+        _ => '/',
+    }
+}
+
+// the cells something flies through on the way to the target, it stops in
+// front of a wall, on the first creature in the way or when it runs out of
+// range. the creature it hit is given back as well
+pub fn projectile_path(
+    scene: &Scene,
+    shooter: usize,
+    from: (isize, isize),
+    to: (isize, isize),
+    range: isize,
+) -> (Vec<(isize, isize)>, Option<usize>) {
+    let column_count = scene.game_map.map_info.column_count as isize;
+
+    let line = line_of_sight(&scene.game_map, from, to);
+
+    let mut path = vec![];
+
+    for pos in line.path {
+        let distance = (pos.0 - from.0).pow(2) + (pos.1 - from.1).pow(2);
+
+        if distance > range.pow(2) || blocks_sight(&scene.game_map, pos) {
+            break;
+        }
+
+        path.push(pos);
+
+        let index = (pos.0 + column_count * pos.1) as usize;
+
+        match creature_at(scene, index) {
+            Some(hit) if hit != shooter => return (path, Some(hit)),
+            _ => {}
+        }
+    }
+
+    (path, None)
+}
+
+// is the first thing a shot from the shooter would hit the target
+pub fn clear_shot(scene: &Scene, shooter: usize, target: usize) -> bool {
+    let ranged = if let Some(ranged) = ranged_of(&scene.components, shooter) {
+        ranged
+    } else {
+        return false;
+    };
+
+    let (from, to) = match (position(scene, shooter), position(scene, target)) {
+        (Some(from), Some(to)) => (from, to),
+        _ => return false,
+    };
+
+    let (_, hit) = projectile_path(scene, shooter, from, to, ranged.range);
+
+    hit == Some(target)
+}
+
+// shoot whatever the shooter has at a cell, returns false if it has nothing
+// to shoot with
+pub fn fire(scene: &mut Scene, shooter: usize, target_index: usize) -> bool {
+    let ranged = if let Some(ranged) = ranged_of(&scene.components, shooter) {
+        ranged
+    } else {
+        return false;
+    };
+
+    let from = if let Some(from) = position(scene, shooter) {
+        from
+    } else {
+        return false;
+    };
+
+    let column_count = scene.game_map.map_info.column_count as isize;
+
+    let to = (
+        target_index as isize % column_count,
+        target_index as isize / column_count,
+    );
+
+    let (path, hit) = projectile_path(scene, shooter, from, to, ranged.range);
+
+    let shooter_index = (from.0 + column_count * from.1) as usize;
+
+    make_noise(scene, shooter, shooter_index, SHOT_NOISE);
+
+    scene
+        .projectiles
+        .push(Projectile::new(path, arrow_glyph(from, to)));
+
+    if let Some(hit) = hit {
+        attack_with(scene, shooter, hit, ranged.power, "shoots");
+    } else {
+        let name = name_of(scene, shooter);
+
+        scene.add_message(format!("{} shoots and misses", name));
+    }
+
+    true
+}
+
+// one item off the top of a stack, anything else is thrown whole
+fn take_one(
+    scene: &mut Scene,
+    entitys: &mut Entitys,
+    owner: usize,
+    slot: usize,
+    item_id: usize,
+) -> usize {
+    let split = match scene.components.stack.get_mut(&item_id) {
+        Some(stack) if stack.count > 1 => {
+            stack.count -= 1;
+
+            Some(stack.max)
+        }
+        _ => None,
+    };
+
+    let max = if let Some(max) = split {
+        max
+    } else {
+        unequip(scene, owner, item_id);
+
+        if let Some(inventory) = scene.components.inventory.get_mut(&owner) {
+            inventory.items.remove(slot);
+        }
+
+        return item_id;
+    };
+
+    let (kind, glyph) = {
+        let item = scene.components.item.get(&item_id).unwrap();
+
+        (item.kind, item.glyph)
+    };
+
+    let name = name_of(scene, item_id);
+
+    let new_id = entitys.new_id();
+
+    scene.components.name.insert(new_id, Name { value: name });
+    scene.components.item.insert(new_id, Item { kind, glyph });
+    scene
+        .components
+        .stack
+        .insert(new_id, Stack { count: 1, max });
+
+    new_id
+}
+
+// throw an item from a slot at a cell, it lands where it stops
+pub fn throw_item(
+    scene: &mut Scene,
+    entitys: &mut Entitys,
+    owner: usize,
+    slot: usize,
+    target_index: usize,
+) -> bool {
+    let item_id = if let Some(item_id) = item_in_slot(scene, owner, slot) {
+        item_id
+    } else {
+        return false;
+    };
+
+    let from = if let Some(from) = position(scene, owner) {
+        from
+    } else {
+        return false;
+    };
+
+    let column_count = scene.game_map.map_info.column_count as isize;
+
+    let to = (
+        target_index as isize % column_count,
+        target_index as isize / column_count,
+    );
+
+    let thrown = take_one(scene, entitys, owner, slot, item_id);
+
+    let glyph = scene.components.item.get(&thrown).map_or('?', |i| i.glyph);
+
+    let power = scene
+        .components
+        .ranged
+        .get(&thrown)
+        .map_or(THROW_POWER, |ranged| ranged.power);
+
+    let (path, hit) = projectile_path(scene, owner, from, to, THROW_RANGE);
+
+    // if it could not leave the owners cell it falls at their feet
+    let land = path.last().copied().unwrap_or(from);
+
+    scene.components.render.insert(
+        thrown,
+        Render {
+            index: (land.0 + column_count * land.1) as usize,
+            reper_char: glyph,
+            size: EntitySize::Nothing,
+            visible: true,
+        },
+    );
+
+    scene.projectiles.push(Projectile::new(path, glyph));
+
+    let item_name = name_of(scene, thrown);
+
+    scene.add_message(format!("you throw the {}", item_name));
+
+    if let Some(hit) = hit {
+        attack_with(scene, owner, hit, power, "hits");
+    }
+
+    true
+}
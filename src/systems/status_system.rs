@@ -0,0 +1,216 @@
+use crate::{
+    components::{ComponentStore, StatusEffect, StatusEffects, StatusKind},
+    scenes::Scene,
+};
+
+use super::combat_system::{kill, name_of};
+
+pub fn status_name(kind: StatusKind) -> &'static str {
+    match kind {
+        StatusKind::Poison => "poisoned",
+        StatusKind::Regeneration => "regenerating",
+        StatusKind::Haste => "hasted",
+        StatusKind::Slow => "slowed",
+        StatusKind::Confusion => "confused",
+        StatusKind::Blindness => "blind",
+        StatusKind::Paralysis => "paralysed",
+        StatusKind::Invisibility => "invisible",
+    }
+}
+
+pub fn has_status(
+    components: &ComponentStore,
+    entity_id: usize,
+    kind: StatusKind,
+) -> bool {
+    components
+        .status_effects
+        .get(&entity_id)
+        .is_some_and(|status| status.effects.iter().any(|e| e.kind == kind))
+}
+
+// the player is told about themselves and about anything else by name
+fn status_message(
+    scene: &mut Scene,
+    entity_id: usize,
+    player: &str,
+    other: &str,
+) {
+    if entity_id == scene.player {
+        scene.add_message(player.to_string());
+    } else {
+        let name = name_of(scene, entity_id);
+
+        scene.add_message(format!("{} {}", name, other));
+    }
+}
+
+fn on_apply(scene: &mut Scene, entity_id: usize, effect: &StatusEffect) {
+    let name = status_name(effect.kind);
+
+    status_message(
+        scene,
+        entity_id,
+        &format!("you are {}", name),
+        &format!("is {}", name),
+    );
+
+    // whatever the ai was planning no longer makes sense
+    if let StatusKind::Confusion | StatusKind::Paralysis = effect.kind {
+        scene.components.ai_path.remove(&entity_id);
+    }
+}
+
+// runs once a turn while the effect lasts
+fn on_tick(scene: &mut Scene, entity_id: usize, effect: &StatusEffect) {
+    let change = match effect.kind {
+        StatusKind::Poison => -effect.potency,
+        StatusKind::Regeneration => effect.potency,
+        _ => return,
+    };
+
+    let dead = if let Some(health) = scene.components.health.get_mut(&entity_id)
+    {
+        health.cur_value = (health.cur_value + change).min(health.max_value);
+
+        health.cur_value <= 0
+    } else {
+        return;
+    };
+
+    if dead {
+        kill(scene, entity_id, entity_id);
+
+        if scene.is_player(entity_id) {
+            scene.death_cause = Some(String::from("killed by poison"));
+        }
+    }
+}
+
+fn on_expire(scene: &mut Scene, entity_id: usize, effect: &StatusEffect) {
+    let name = status_name(effect.kind);
+
+    status_message(
+        scene,
+        entity_id,
+        &format!("you are no longer {}", name),
+        &format!("is no longer {}", name),
+    );
+}
+
+// give an entity an effect. an effect it already has is made to last as long
+// as the longer of the two and the stronger potency is kept, poison is the
+// one that adds up. haste and slow cancel each other out
+pub fn add_status(
+    scene: &mut Scene,
+    entity_id: usize,
+    kind: StatusKind,
+    turns: usize,
+    potency: isize,
+) {
+    let opposite = match kind {
+        StatusKind::Haste => Some(StatusKind::Slow),
+        StatusKind::Slow => Some(StatusKind::Haste),
+        _ => None,
+    };
+
+    if let Some(opposite) = opposite {
+        let cancelled = scene
+            .components
+            .status_effects
+            .get_mut(&entity_id)
+            .and_then(|status| {
+                let index =
+                    status.effects.iter().position(|e| e.kind == opposite)?;
+
+                Some(status.effects.remove(index))
+            });
+
+        if let Some(cancelled) = cancelled {
+            on_expire(scene, entity_id, &cancelled);
+
+            return;
+        }
+    }
+
+    let status = scene
+        .components
+        .status_effects
+        .entry(entity_id)
+        .or_insert_with(|| StatusEffects {
+            effects: Vec::new(),
+        });
+
+    if let Some(effect) = status.effects.iter_mut().find(|e| e.kind == kind) {
+        effect.turns = effect.turns.max(turns);
+
+        effect.potency = if kind == StatusKind::Poison {
+            effect.potency + potency
+        } else {
+            effect.potency.max(potency)
+        };
+
+        return;
+    }
+
+    let effect = StatusEffect {
+        kind,
+        turns,
+        potency,
+    };
+
+    status.effects.push(effect);
+
+    on_apply(scene, entity_id, &effect);
+}
+
+// tick every effect on everything once and take away the ones that ran out
+pub fn status_system(scene: &mut Scene) {
+    let mut entity_ids: Vec<usize> =
+        scene.components.status_effects.keys().copied().collect();
+
+    entity_ids.sort_unstable();
+
+    for entity_id in entity_ids {
+        let effects = if let Some(status) =
+            scene.components.status_effects.get(&entity_id)
+        {
+            status.effects.clone()
+        } else {
+            continue;
+        };
+
+        for effect in effects.iter() {
+            on_tick(scene, entity_id, effect);
+        }
+
+        // the entity could have died from a tick and lost its effects
+        let status = if let Some(status) =
+            scene.components.status_effects.get_mut(&entity_id)
+        {
+            status
+        } else {
+            continue;
+        };
+
+        let mut expired = vec![];
+
+        status.effects.retain_mut(|effect| {
+            effect.turns = effect.turns.saturating_sub(1);
+
+            if effect.turns == 0 {
+                expired.push(*effect);
+            }
+
+            effect.turns > 0
+        });
+
+        if status.effects.is_empty() {
+            scene.components.status_effects.remove(&entity_id);
+        }
+
+        for effect in expired.iter() {
+            on_expire(scene, entity_id, effect);
+        }
+    }
+}
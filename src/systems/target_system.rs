@@ -0,0 +1,166 @@
+use crate::{
+    components::ItemKind,
+    consumables::{Targeting, CONSUMABLES},
+    los::can_see,
+    scenes::Scene,
+    stats::ranged_of,
+    LoopState, TargetAction,
+};
+
+use super::{
+    combat_system::is_hostile, item_system::item_in_slot,
+    ranged_system::THROW_RANGE,
+};
+
+// how far away the thing being aimed can reach, none if it is not aimed at all
+pub fn target_range(scene: &Scene, action: TargetAction) -> Option<isize> {
+    match action {
+        TargetAction::UseItem(slot) => {
+            let item_id = item_in_slot(scene, scene.player, slot)?;
+
+            match scene.components.item.get(&item_id)?.kind {
+                ItemKind::Consumable(def_index) => {
+                    match CONSUMABLES[def_index].targeting {
+                        Targeting::Cell { range } => Some(range),
+                        Targeting::User => None,
+                    }
+                }
+                ItemKind::Equipment => None,
+            }
+        }
+        TargetAction::Fire => {
+            ranged_of(&scene.components, scene.player).map(|r| r.range)
+        }
+        TargetAction::Throw(slot) => {
+            item_in_slot(scene, scene.player, slot).map(|_| THROW_RANGE)
+        }
+    }
+}
+
+// a target has to be in view, in range and not behind a wall
+pub fn valid_target(
+    scene: &Scene,
+    from_id: usize,
+    index: usize,
+    range: isize,
+) -> bool {
+    let from_index = if let Some(render) = scene.components.render.get(&from_id)
+    {
+        render.index
+    } else {
+        return false;
+    };
+
+    if !scene.game_map.render_map[index].lit {
+        return false;
+    }
+
+    let column_count = scene.game_map.map_info.column_count as isize;
+
+    let from = (
+        from_index as isize % column_count,
+        from_index as isize / column_count,
+    );
+    let to = (index as isize % column_count, index as isize / column_count);
+
+    (to.0 - from.0).pow(2) + (to.1 - from.1).pow(2) <= range.pow(2)
+        && can_see(&scene.game_map, from, to)
+}
+
+// the cursor stays on the map
+pub fn move_cursor(scene: &mut Scene, adjust: (isize, isize)) {
+    let column_count = scene.game_map.map_info.column_count as isize;
+    let row_count = scene.game_map.map_info.row_count as isize;
+
+    let x = (scene.cursor as isize % column_count + adjust.0)
+        .max(0)
+        .min(column_count - 1);
+    let y = (scene.cursor as isize / column_count + adjust.1)
+        .max(0)
+        .min(row_count - 1);
+
+    scene.cursor = (x + column_count * y) as usize;
+}
+
+// the cells of every hostile the player could aim at, closest first
+fn hostile_targets(scene: &Scene, range: isize) -> Vec<usize> {
+    let player = scene.player;
+
+    let player_index =
+        if let Some(render) = scene.components.render.get(&player) {
+            render.index as isize
+        } else {
+            return vec![];
+        };
+
+    let column_count = scene.game_map.map_info.column_count as isize;
+
+    let mut targets: Vec<(isize, usize)> = scene
+        .components
+        .health
+        .keys()
+        .filter(|ent_id| is_hostile(scene, player, **ent_id))
+        .filter_map(|ent_id| scene.components.render.get(ent_id))
+        .filter(|render| valid_target(scene, player, render.index, range))
+        .map(|render| {
+            let index = render.index as isize;
+
+            let dx = index % column_count - player_index % column_count;
+            let dy = index / column_count - player_index / column_count;
+
+            (dx * dx + dy * dy, render.index)
+        })
+        .collect();
+
+    targets.sort_unstable();
+
+    targets.into_iter().map(|(_, index)| index).collect()
+}
+
+// jump the cursor to the next hostile in view, going back to the closest one
+// after the last
+pub fn cycle_target(scene: &mut Scene, action: TargetAction) {
+    let range = if let Some(range) = target_range(scene, action) {
+        range
+    } else {
+        return;
+    };
+
+    let targets = hostile_targets(scene, range);
+
+    if targets.is_empty() {
+        return;
+    }
+
+    let next = match targets.iter().position(|index| *index == scene.cursor) {
+        Some(current) => (current + 1) % targets.len(),
+        None => 0,
+    };
+
+    scene.cursor = targets[next];
+}
+
+// put the cursor on the closest hostile or on the player if there is none
+pub fn start_targeting(scene: &mut Scene, action: TargetAction) -> LoopState {
+    let range = if let Some(range) = target_range(scene, action) {
+        range
+    } else {
+        return LoopState::Wait;
+    };
+
+    let targets = hostile_targets(scene, range);
+
+    scene.cursor = if let Some(closest) = targets.first() {
+        *closest
+    } else if let Some(render) = scene.components.render.get(&scene.player) {
+        render.index
+    } else {
+        return LoopState::Wait;
+    };
+
+    scene.add_message(String::from(
+        "pick a target with the arrow keys or tab and press enter",
+    ));
+
+    LoopState::Targeting(action)
+}
@@ -0,0 +1,159 @@
+use crate::{
+    components::Energy, entitys::Entitys, scenes::Scene,
+    stats::effective_stats, LoopState,
+};
+
+use super::{
+    ai_system::ai_system, noise_system::noise_system,
+    progression_system::tick_cooldowns, status_system::status_system,
+};
+
+// what most actions cost, an entity with normal speed gets one a turn
+pub const ACTION_COST: isize = 100;
+
+// picking things up and putting them down, only players do either
+pub const QUICK_ACTION_COST: isize = 50;
+
+fn energy_of(scene: &Scene, entity_id: usize) -> isize {
+    scene
+        .components
+        .energy
+        .get(&entity_id)
+        .map_or(0, |energy| energy.value)
+}
+
+fn spend_energy(scene: &mut Scene, entity_id: usize, cost: isize) {
+    if let Some(energy) = scene.components.energy.get_mut(&entity_id) {
+        energy.value -= cost;
+    }
+}
+
+// one turn of time, everything gains energy at its own speed and the ais act
+// for as long as they have enough. they go in rounds, lowest id first, so a
+// fast ai dose not take all of its actions before the others get one
+fn tick(scene: &mut Scene, entitys: &mut Entitys) {
+    scene.turns += 1;
+
+    scene.process_map_changes();
+
+    noise_system(scene);
+
+    let mut actors: Vec<usize> = scene.components.ai.keys().copied().collect();
+
+    actors.extend(
+        scene
+            .players
+            .iter()
+            .copied()
+            .filter(|player| !scene.is_dead(*player)),
+    );
+
+    for actor in actors {
+        let speed = effective_stats(&scene.components, actor).speed;
+
+        scene
+            .components
+            .energy
+            .entry(actor)
+            .or_insert(Energy { value: 0 })
+            .value += speed;
+    }
+
+    loop {
+        let mut ready: Vec<usize> = scene
+            .components
+            .ai
+            .keys()
+            .copied()
+            .filter(|ai_id| energy_of(scene, *ai_id) >= ACTION_COST)
+            .collect();
+
+        if ready.is_empty() || scene.game_over() {
+            break;
+        }
+
+        ready.sort_unstable();
+
+        ai_system(scene, entitys, &ready);
+
+        // ais never pick anything up or put it down, the only quick actions
+        // there are, so everything they do costs a full action. an ai that
+        // learns to do either has to pay QUICK_ACTION_COST for it here
+        for ai_id in ready {
+            spend_energy(scene, ai_id, ACTION_COST);
+        }
+
+        scene.process_map_changes();
+    }
+
+    status_system(scene);
+
+    tick_cooldowns(scene);
+}
+
+// the next player still standing round from the one who just acted that has
+// the energy to act, with one player that is always them again
+fn next_player(scene: &Scene) -> Option<usize> {
+    let count = scene.players.len();
+
+    let current = scene
+        .players
+        .iter()
+        .position(|player| *player == scene.player)
+        .unwrap_or(0);
+
+    (1..=count)
+        .map(|offset| scene.players[(current + offset) % count])
+        .find(|player| {
+            !scene.is_dead(*player) && energy_of(scene, *player) >= ACTION_COST
+        })
+}
+
+pub fn player_ready(scene: &Scene) -> bool {
+    energy_of(scene, scene.player) >= ACTION_COST
+}
+
+// take what the action the player just took costs out of their energy
+pub fn pay_for_action(scene: &mut Scene) {
+    let player = scene.player;
+
+    let cost = std::mem::replace(&mut scene.action_cost, ACTION_COST);
+
+    scene
+        .components
+        .energy
+        .entry(player)
+        .or_insert(Energy { value: ACTION_COST })
+        .value -= cost;
+}
+
+// the player has just acted, pay for it and run time forward until one of the
+// players has the energy to act. a fast player can act again straight away
+pub fn turn_system(scene: &mut Scene, entitys: &mut Entitys) {
+    pay_for_action(scene);
+
+    while !scene.game_over() {
+        if let Some(next) = next_player(scene) {
+            scene.player = next;
+
+            break;
+        }
+
+        tick(scene, entitys);
+    }
+}
+
+// in real time the world moves on by one tick whether the player did anything
+// or not. a player who is not doing anything can only save up enough energy
+// for one action
+pub fn real_time_system(scene: &mut Scene, entitys: &mut Entitys) {
+    if scene.loop_state == LoopState::Run {
+        pay_for_action(scene);
+    }
+
+    tick(scene, entitys);
+
+    if let Some(energy) = scene.components.energy.get_mut(&scene.player) {
+        energy.value = energy.value.min(ACTION_COST);
+    }
+}